@@ -2,12 +2,18 @@ use std::process::Command;
 
 use anyhow::{anyhow, Context};
 
+use crate::adapters::pipewire::{PwAvailability, PwDump, PwNode};
+
 #[derive(Debug, Clone)]
 pub struct AudioSink {
     pub id: String,
     pub name: String,
+    /// PipeWire `node.name`, e.g. `alsa_output.pci-0000_01_00.1.hdmi-stereo`.
+    pub node_name: String,
     pub is_default: bool,
     pub is_hdmi: bool,
+    pub volume: Option<f32>,
+    pub muted: bool,
 }
 
 impl AudioSink {
@@ -17,48 +23,152 @@ impl AudioSink {
     }
 }
 
-#[derive(Debug, Default)]
-pub struct AudioAdapter;
+/// A capture device such as a laptop or webcam microphone.
+#[derive(Debug, Clone)]
+pub struct AudioSource {
+    pub id: String,
+    pub name: String,
+    pub is_default: bool,
+    pub volume: Option<f32>,
+    pub muted: bool,
+}
 
-impl AudioAdapter {
-    pub fn list_sink_objects(&self) -> anyhow::Result<Vec<AudioSink>> {
-        let output = Command::new("wpctl")
-            .arg("status")
-            .output()
-            .context("failed to run `wpctl status`")?;
-        if !output.status.success() {
-            return Err(anyhow!("`wpctl status` exited with non-zero status"));
+impl AudioSource {
+    pub fn display_line(&self) -> String {
+        let default_mark = if self.is_default { "*" } else { " " };
+        format!("{default_mark} {}. {}", self.id, self.name)
+    }
+}
+
+/// An application playing audio, and the sink it currently plays on.
+#[derive(Debug, Clone)]
+pub struct AudioStream {
+    /// pipewire-pulse sink-input index, used to move the stream.
+    pub serial: u64,
+    pub application: String,
+    pub binary: Option<String>,
+    pub title: Option<String>,
+    pub sink_id: Option<String>,
+}
+
+impl AudioStream {
+    pub fn label(&self) -> String {
+        match &self.title {
+            Some(title) if *title != self.application => format!("{} — {title}", self.application),
+            _ => self.application.clone(),
         }
+    }
+}
 
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        Ok(parse_audio_sinks(&stdout))
+/// A sound card with the profiles it offers. Only one profile is active at
+/// a time, and it decides which sinks and sources the card exposes.
+#[derive(Debug, Clone)]
+pub struct AudioCard {
+    pub id: String,
+    pub name: String,
+    /// PipeWire `device.name`, e.g. `alsa_card.pci-0000_01_00.1`.
+    pub device_name: String,
+    pub profiles: Vec<CardProfile>,
+    pub active_profile: Option<u32>,
+}
+
+impl AudioCard {
+    pub fn active_profile(&self) -> Option<&CardProfile> {
+        let active = self.active_profile?;
+        self.profiles.iter().find(|profile| profile.index == active)
     }
+}
 
-    pub fn list_sinks(&self) -> anyhow::Result<Vec<String>> {
-        Ok(self
-            .list_sink_objects()?
-            .into_iter()
-            .map(|sink| sink.display_line())
-            .collect())
+#[derive(Debug, Clone)]
+pub struct CardProfile {
+    pub index: u32,
+    /// e.g. `output:hdmi-stereo-extra1`.
+    pub name: String,
+    pub description: String,
+    pub available: PwAvailability,
+    pub priority: u32,
+    pub ports: Vec<CardPort>,
+}
+
+impl CardProfile {
+    pub fn display_line(&self) -> String {
+        let available = match self.available {
+            PwAvailability::Yes => "available",
+            PwAvailability::No => "unavailable",
+            PwAvailability::Unknown => "availability unknown",
+        };
+        let ports = self
+            .ports
+            .iter()
+            .map(CardPort::display_line)
+            .collect::<Vec<_>>();
+        if ports.is_empty() {
+            format!("{} ({available})", self.name)
+        } else {
+            format!("{} ({available}; {})", self.name, ports.join(", "))
+        }
     }
+}
+
+/// A connector or jack of a card, e.g. `hdmi-output-1`.
+#[derive(Debug, Clone)]
+pub struct CardPort {
+    pub name: String,
+    pub plugged: PwAvailability,
+}
+
+impl CardPort {
+    pub fn display_line(&self) -> String {
+        match self.plugged {
+            PwAvailability::Yes => format!("{} plugged", self.name),
+            PwAvailability::No => format!("{} unplugged", self.name),
+            PwAvailability::Unknown => self.name.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct AudioDevices {
+    pub sinks: Vec<AudioSink>,
+    pub sources: Vec<AudioSource>,
+    pub streams: Vec<AudioStream>,
+    pub cards: Vec<AudioCard>,
+}
 
-    pub fn find_first_hdmi_sink(&self) -> anyhow::Result<Option<String>> {
-        let sinks = self.list_sink_objects()?;
-        Ok(sinks
-            .into_iter()
-            .find(|sink| sink.is_hdmi)
-            .map(|sink| sink.display_line()))
+#[derive(Debug, Default)]
+pub struct AudioAdapter;
+
+impl AudioAdapter {
+    pub fn list_sink_objects(&self) -> anyhow::Result<Vec<AudioSink>> {
+        Ok(sinks_from_dump(&PwDump::capture()?))
     }
 
-    pub fn select_first_hdmi_sink(&self) -> anyhow::Result<Option<String>> {
-        let sink = self
-            .list_sink_objects()?
-            .into_iter()
-            .find(|sink| sink.is_hdmi);
-        if let Some(sink) = &sink {
-            let _ = self.set_default_and_move_streams_by_id(&sink.id)?;
+    /// Sinks, sources, playback streams and cards from a single pw-dump run.
+    pub fn list_devices(&self) -> anyhow::Result<AudioDevices> {
+        let dump = PwDump::capture()?;
+        Ok(AudioDevices {
+            sinks: sinks_from_dump(&dump),
+            sources: sources_from_dump(&dump),
+            streams: streams_from_dump(&dump),
+            cards: cards_from_dump(&dump),
+        })
+    }
+
+    /// Moves one playback stream onto a sink, leaving every other stream
+    /// where it is.
+    pub fn move_stream(&self, stream: &AudioStream, sink: &AudioSink) -> anyhow::Result<()> {
+        let serial = stream.serial.to_string();
+        let status = Command::new("pactl")
+            .args(["move-sink-input", &serial, &sink.node_name])
+            .status()
+            .context("failed to run `pactl move-sink-input`")?;
+        if !status.success() {
+            return Err(anyhow!(
+                "`pactl move-sink-input {serial} {}` exited with non-zero status",
+                sink.node_name
+            ));
         }
-        Ok(sink.map(|sink| sink.display_line()))
+        Ok(())
     }
 
     pub fn set_default_by_id(&self, id: &str) -> anyhow::Result<()> {
@@ -72,239 +182,136 @@ impl AudioAdapter {
         Ok(())
     }
 
-    pub fn set_default_by_name(&self, sink_line: &str) -> anyhow::Result<()> {
-        let id = extract_first_number(sink_line)
-            .ok_or_else(|| anyhow!("could not parse sink id from line: {sink_line}"))?;
-
-        self.set_default_by_id(&id)
-    }
-
-    pub fn set_default_and_move_streams_by_id(&self, id: &str) -> anyhow::Result<usize> {
-        self.set_default_by_id(id)?;
-
-        let sink_name = match self.sink_node_name_by_id(id)? {
-            Some(name) => name,
-            None => return Ok(0),
-        };
-
-        let stream_ids = self.active_sink_input_ids().unwrap_or_default();
-        let mut moved = 0usize;
-        for stream_id in stream_ids {
-            let status = Command::new("pactl")
-                .args(["move-sink-input", &stream_id, &sink_name])
-                .status();
-            if let Ok(status) = status {
-                if status.success() {
-                    moved += 1;
-                }
-            }
-        }
-
-        Ok(moved)
+    /// Sets the volume in percent, capped at 100%.
+    pub fn set_volume(&self, id: &str, percent: u32) -> anyhow::Result<()> {
+        self.wpctl(&["set-volume", "-l", "1.0", id, &format!("{}%", percent.min(100))])
     }
 
-    pub fn try_switch_card_profile_to_laptop(&self) -> anyhow::Result<bool> {
-        let candidates = [
-            "output:analog-stereo+input:analog-stereo",
-            "output:analog-stereo",
-            "analog-stereo",
-        ];
-        self.try_set_card_profile_candidates(&candidates)
+    /// Raises or lowers the volume by `percent` points, capped at 100%.
+    pub fn step_volume(&self, id: &str, percent: i32) -> anyhow::Result<()> {
+        let sign = if percent < 0 { '-' } else { '+' };
+        let step = format!("{}%{sign}", percent.unsigned_abs());
+        self.wpctl(&["set-volume", "-l", "1.0", id, &step])
     }
 
-    pub fn try_switch_card_profile_to_tv(&self) -> anyhow::Result<bool> {
-        let candidates = [
-            "output:hdmi-stereo+input:analog-stereo",
-            "output:hdmi-stereo",
-            "hdmi-stereo",
-        ];
-        self.try_set_card_profile_candidates(&candidates)
+    pub fn toggle_mute(&self, id: &str) -> anyhow::Result<()> {
+        self.wpctl(&["set-mute", id, "toggle"])
     }
 
-    fn try_set_card_profile_candidates(&self, candidates: &[&str]) -> anyhow::Result<bool> {
-        if !Command::new("which")
-            .arg("pactl")
-            .output()
-            .map(|o| o.status.success())
-            .unwrap_or(false)
-        {
-            return Ok(false);
-        }
-
-        let cards = list_pactl_cards()?;
-        if cards.is_empty() {
-            return Ok(false);
-        }
-
-        for card in &cards {
-            for profile in candidates {
-                let status = Command::new("pactl")
-                    .args(["set-card-profile", card, profile])
-                    .status();
-                if let Ok(status) = status {
-                    if status.success() {
-                        return Ok(true);
-                    }
-                }
-            }
+    fn wpctl(&self, args: &[&str]) -> anyhow::Result<()> {
+        let status = Command::new("wpctl")
+            .args(args)
+            .status()
+            .with_context(|| format!("failed to run `wpctl {}`", args[0]))?;
+        if !status.success() {
+            return Err(anyhow!("`wpctl {}` exited with non-zero status", args.join(" ")));
         }
-
-        Ok(false)
+        Ok(())
     }
-}
 
-impl AudioAdapter {
-    fn sink_node_name_by_id(&self, id: &str) -> anyhow::Result<Option<String>> {
-        let output = Command::new("wpctl")
-            .args(["inspect", id])
-            .output()
-            .context("failed to run `wpctl inspect <id>`")?;
-        if !output.status.success() {
-            return Ok(None);
-        }
+    pub fn set_default_by_name(&self, sink_line: &str) -> anyhow::Result<()> {
+        let id = extract_first_number(sink_line)
+            .ok_or_else(|| anyhow!("could not parse sink id from line: {sink_line}"))?;
 
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        Ok(parse_node_name_from_wpctl_inspect(&stdout))
+        self.set_default_by_id(&id)
     }
 
-    fn active_sink_input_ids(&self) -> anyhow::Result<Vec<String>> {
-        if !Command::new("which")
-            .arg("pactl")
-            .output()
-            .map(|o| o.status.success())
-            .unwrap_or(false)
-        {
-            return Ok(Vec::new());
-        }
-
-        let output = Command::new("pactl")
-            .args(["list", "short", "sink-inputs"])
-            .output()
-            .context("failed to run `pactl list short sink-inputs`")?;
-        if !output.status.success() {
-            return Ok(Vec::new());
-        }
-
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        let ids = stdout
-            .lines()
-            .filter_map(|line| line.split_whitespace().next())
-            .map(str::to_string)
-            .collect::<Vec<_>>();
-        Ok(ids)
+    pub fn set_card_profile(&self, card_id: &str, profile_index: u32) -> anyhow::Result<()> {
+        self.wpctl(&["set-profile", card_id, &profile_index.to_string()])
     }
 }
 
-fn list_pactl_cards() -> anyhow::Result<Vec<String>> {
-    let output = Command::new("pactl")
-        .args(["list", "short", "cards"])
-        .output()
-        .context("failed to run `pactl list short cards`")?;
-    if !output.status.success() {
-        return Err(anyhow!("`pactl list short cards` exited with non-zero status"));
-    }
-
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let cards = stdout
-        .lines()
-        .filter_map(|line| {
-            let mut fields = line.split_whitespace();
-            let _id = fields.next()?;
-            let card = fields.next()?;
-            Some(card.to_string())
+/// Sinks in pw-dump order. A sink counts as HDMI when its name, its
+/// description or the card route feeding it says so; the route name does
+/// not depend on the desktop language.
+pub fn sinks_from_dump(dump: &PwDump) -> Vec<AudioSink> {
+    dump.sinks()
+        .map(|node| AudioSink {
+            id: node.id.to_string(),
+            name: node.description.clone(),
+            node_name: node.name.clone(),
+            is_default: dump.default_sink.as_deref() == Some(node.name.as_str()),
+            is_hdmi: is_external_sink(dump, node),
+            volume: node.volume,
+            muted: node.muted,
         })
-        .collect::<Vec<_>>();
-    Ok(cards)
+        .collect()
 }
 
-fn parse_node_name_from_wpctl_inspect(inspect_output: &str) -> Option<String> {
-    inspect_output.lines().find_map(|line| {
-        let marker = "node.name = \"";
-        let idx = line.find(marker)?;
-        let rest = &line[idx + marker.len()..];
-        let end = rest.find('"')?;
-        let name = rest[..end].trim();
-        if name.is_empty() {
-            None
-        } else {
-            Some(name.to_string())
-        }
-    })
+pub fn sources_from_dump(dump: &PwDump) -> Vec<AudioSource> {
+    dump.sources()
+        .map(|node| AudioSource {
+            id: node.id.to_string(),
+            name: node.description.clone(),
+            is_default: dump.default_source.as_deref() == Some(node.name.as_str()),
+            volume: node.volume,
+            muted: node.muted,
+        })
+        .collect()
 }
 
-fn parse_audio_sinks(status_output: &str) -> Vec<AudioSink> {
-    let mut sinks = Vec::new();
-    let mut in_audio = false;
-    let mut in_sinks = false;
-
-    for raw in status_output.lines() {
-        let line = raw.trim();
-
-        if line == "Audio" {
-            in_audio = true;
-            in_sinks = false;
-            continue;
-        }
-
-        if in_audio && (line == "Video" || line == "Settings") {
-            break;
-        }
-
-        if !in_audio {
-            continue;
-        }
-
-        if line.starts_with("├─ Sinks:") || line.starts_with("└─ Sinks:") {
-            in_sinks = true;
-            continue;
-        }
-
-        if in_sinks
-            && (line.starts_with("├─ Sources:")
-                || line.starts_with("└─ Sources:")
-                || line.starts_with("├─ Streams:")
-                || line.starts_with("└─ Streams:")
-                || line.starts_with("├─ Filters:")
-                || line.starts_with("└─ Filters:"))
-        {
-            break;
-        }
-
-        if in_sinks {
-            if let Some(sink) = parse_sink_line(line) {
-                sinks.push(sink);
-            }
-        }
-    }
-
-    sinks
+/// Streams without a pulse serial cannot be moved and are left out.
+pub fn streams_from_dump(dump: &PwDump) -> Vec<AudioStream> {
+    dump.playback_streams()
+        .filter_map(|node| {
+            Some(AudioStream {
+                serial: node.serial?,
+                application: node
+                    .application
+                    .clone()
+                    .unwrap_or_else(|| node.description.clone()),
+                binary: node.binary.clone(),
+                title: node.media_title.clone(),
+                sink_id: dump.sink_of(node).map(|sink| sink.id.to_string()),
+            })
+        })
+        .collect()
 }
 
-fn parse_sink_line(line: &str) -> Option<AudioSink> {
-    if !line.contains('.') {
-        return None;
-    }
+/// Cards with every profile they offer and the plug state of the ports each
+/// profile drives.
+pub fn cards_from_dump(dump: &PwDump) -> Vec<AudioCard> {
+    dump.devices
+        .iter()
+        .map(|device| AudioCard {
+            id: device.id.to_string(),
+            name: device.description.clone(),
+            device_name: device.name.clone(),
+            profiles: device
+                .profiles
+                .iter()
+                .map(|profile| CardProfile {
+                    index: profile.index,
+                    name: profile.name.clone(),
+                    description: profile.description.clone(),
+                    available: profile.available,
+                    priority: profile.priority,
+                    ports: device
+                        .routes_for(profile)
+                        .map(|route| CardPort {
+                            name: route.name.clone(),
+                            plugged: route.available,
+                        })
+                        .collect(),
+                })
+                .collect(),
+            active_profile: device.active_profile.as_ref().map(|profile| profile.index),
+        })
+        .collect()
+}
 
-    let is_default = line.contains('*');
-    let id = extract_first_number(line)?;
-
-    let after_dot = line.split_once(". ")?.1.trim();
-    let name = after_dot
-        .split(" [")
-        .next()
-        .map(str::trim)
-        .unwrap_or(after_dot)
-        .to_string();
-    if name.is_empty() {
-        return None;
-    }
+fn is_external_sink(dump: &PwDump, node: &PwNode) -> bool {
+    is_external_sink_name(&node.name)
+        || is_external_sink_name(&node.description)
+        || dump
+            .active_route(node)
+            .is_some_and(|route| is_external_sink_name(&route.name))
+}
 
-    Some(AudioSink {
-        id,
-        is_hdmi: name.to_ascii_lowercase().contains("hdmi"),
-        is_default,
-        name,
-    })
+/// HDMI and DisplayPort sinks both carry audio to an external display.
+fn is_external_sink_name(name: &str) -> bool {
+    let lower = name.to_ascii_lowercase();
+    lower.contains("hdmi") || lower.contains("displayport")
 }
 
 fn extract_first_number(input: &str) -> Option<String> {
@@ -323,9 +330,30 @@ fn extract_first_number(input: &str) -> Option<String> {
     }
 }
 
+#[cfg(test)]
+pub mod fixtures {
+    use super::AudioSink;
+
+    pub fn sink(id: &str, node_name: &str, is_default: bool, is_hdmi: bool) -> AudioSink {
+        AudioSink {
+            id: id.to_string(),
+            name: format!("sink {id}"),
+            node_name: node_name.to_string(),
+            is_default,
+            is_hdmi,
+            volume: None,
+            muted: false,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{extract_first_number, parse_audio_sinks, parse_node_name_from_wpctl_inspect};
+    use super::{
+        cards_from_dump, extract_first_number, sinks_from_dump, sources_from_dump,
+        streams_from_dump,
+    };
+    use crate::adapters::pipewire::parse_pw_dump;
 
     #[test]
     fn extracts_digits() {
@@ -334,40 +362,90 @@ mod tests {
         assert_eq!(extract_first_number("no digits"), None);
     }
 
+    const LAPTOP_DUMP: &str = include_str!("../../tests/fixtures/pw-dump-laptop-nvidia-hdmi.json");
+
     #[test]
-    fn parses_audio_sinks_section() {
-        let status = r#"
-Audio
- ├─ Devices:
- │      52. Built-in Audio                      [alsa]
- │
- ├─ Sinks:
- │  *   83. Built-in Audio Digital Stereo (HDMI) [vol: 0.34]
- │      91. Built-in Audio Analog Stereo [vol: 0.20]
- │
- ├─ Sources:
- │  *   71. Built-in Audio Analog Stereo        [vol: 1.00]
-"#;
-
-        let sinks = parse_audio_sinks(status);
+    fn builds_sinks_from_pw_dump() {
+        let dump = parse_pw_dump(LAPTOP_DUMP).unwrap();
+        let sinks = sinks_from_dump(&dump);
         assert_eq!(sinks.len(), 2);
-        assert_eq!(sinks[0].id, "83");
+        assert_eq!(sinks[0].id, "48");
+        assert_eq!(sinks[0].name, "Built-in Audio Analog Stereo");
         assert!(sinks[0].is_default);
-        assert!(sinks[0].is_hdmi);
-        assert_eq!(sinks[1].id, "91");
+        assert!(!sinks[0].is_hdmi);
+        assert_eq!(sinks[1].id, "61");
+        assert_eq!(
+            sinks[1].node_name,
+            "alsa_output.pci-0000_01_00.1.hdmi-stereo-extra1"
+        );
         assert!(!sinks[1].is_default);
+        assert!(sinks[1].is_hdmi);
+        assert_eq!(sinks[0].volume.map(|v| (v * 100.0).round()), Some(70.0));
+        assert!(sinks[1].muted);
+    }
+
+    #[test]
+    fn builds_sources_from_pw_dump() {
+        let dump = parse_pw_dump(LAPTOP_DUMP).unwrap();
+        let sources = sources_from_dump(&dump);
+        assert_eq!(sources.len(), 1);
+        assert_eq!(sources[0].id, "49");
+        assert!(sources[0].is_default);
+        assert_eq!(sources[0].volume.map(|v| (v * 100.0).round()), Some(90.0));
     }
 
     #[test]
-    fn parses_node_name_from_inspect() {
-        let inspect = r#"
-id 83, type PipeWire:Interface:Node
-  * media.class = "Audio/Sink"
-  * node.name = "alsa_output.pci-0000_00_1f.3.hdmi-stereo"
-"#;
+    fn builds_streams_from_pw_dump() {
+        let dump = parse_pw_dump(LAPTOP_DUMP).unwrap();
+        let streams = streams_from_dump(&dump);
+        assert_eq!(
+            streams
+                .iter()
+                .map(|stream| (stream.label(), stream.serial, stream.sink_id.as_deref()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("Firefox — AudioStream".to_string(), 512, Some("48")),
+                ("mpv — Big Buck Bunny".to_string(), 530, Some("61")),
+            ]
+        );
+    }
+
+    #[test]
+    fn marks_localized_hdmi_sink_by_route() {
+        let dump = parse_pw_dump(include_str!("../../tests/fixtures/pw-dump-desktop-de.json"))
+            .unwrap();
+        let sinks = sinks_from_dump(&dump);
+        assert_eq!(
+            sinks
+                .iter()
+                .map(|sink| (sink.id.as_str(), sink.is_default, sink.is_hdmi))
+                .collect::<Vec<_>>(),
+            vec![("63", false, false), ("66", true, true)]
+        );
+    }
+
+    #[test]
+    fn lists_card_profiles_with_port_state() {
+        let dump = parse_pw_dump(LAPTOP_DUMP).unwrap();
+        let cards = cards_from_dump(&dump);
+        assert_eq!(cards.len(), 2);
+        let nvidia = &cards[1];
+        assert_eq!(nvidia.device_name, "alsa_card.pci-0000_01_00.1");
+        assert_eq!(
+            nvidia.active_profile().map(|profile| profile.name.as_str()),
+            Some("output:hdmi-stereo-extra1")
+        );
         assert_eq!(
-            parse_node_name_from_wpctl_inspect(inspect),
-            Some("alsa_output.pci-0000_00_1f.3.hdmi-stereo".to_string())
+            nvidia
+                .profiles
+                .iter()
+                .map(|profile| profile.display_line())
+                .collect::<Vec<_>>(),
+            vec![
+                "off (available)",
+                "output:hdmi-stereo (unavailable; hdmi-output-0 unplugged)",
+                "output:hdmi-stereo-extra1 (available; hdmi-output-1 plugged)",
+            ]
         );
     }
 }
@@ -1,5 +1,7 @@
 pub mod audio;
 pub mod niri;
+pub mod niri_ipc;
+pub mod pipewire;
 pub mod portal;
 pub mod system;
 pub mod wl_mirror;
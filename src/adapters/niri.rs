@@ -1,11 +1,18 @@
-use std::process::Command;
-
-use anyhow::{anyhow, Context};
-use serde::Deserialize;
 use std::collections::BTreeMap;
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Default)]
-pub struct NiriAdapter;
+use crate::adapters::niri_ipc::{
+    Action, ModeToSet, NiriEvent, NiriIpcError, NiriSocket, OutputAction, PositionToSet, Request,
+    ScaleToSet, WorkspaceReferenceArg,
+};
+
+#[derive(Debug, Clone)]
+pub struct NiriAdapter {
+    socket: NiriSocket,
+}
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct NiriLogical {
@@ -13,147 +20,678 @@ pub struct NiriLogical {
     pub y: i32,
     pub width: i32,
     pub height: i32,
+    #[serde(default = "default_scale")]
+    pub scale: f64,
+    #[serde(default)]
+    pub transform: NiriTransform,
+}
+
+fn default_scale() -> f64 {
+    1.0
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+pub enum NiriTransform {
+    #[default]
+    Normal,
+    #[serde(rename = "90", alias = "_90")]
+    Rotate90,
+    #[serde(rename = "180", alias = "_180")]
+    Rotate180,
+    #[serde(rename = "270", alias = "_270")]
+    Rotate270,
+    Flipped,
+    // niri's IPC spells these `Flipped90`; the kebab-case names are its CLI
+    // and KDL form, accepted here for snapshots saved by older releases.
+    #[serde(alias = "flipped-90")]
+    Flipped90,
+    #[serde(alias = "flipped-180")]
+    Flipped180,
+    #[serde(alias = "flipped-270")]
+    Flipped270,
+}
+
+impl NiriTransform {
+    pub const ALL: [NiriTransform; 8] = [
+        NiriTransform::Normal,
+        NiriTransform::Rotate90,
+        NiriTransform::Rotate180,
+        NiriTransform::Rotate270,
+        NiriTransform::Flipped,
+        NiriTransform::Flipped90,
+        NiriTransform::Flipped180,
+        NiriTransform::Flipped270,
+    ];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|transform| transform.as_str().eq_ignore_ascii_case(name))
+    }
+
+    /// Next rotation step clockwise, keeping the flip.
+    pub fn rotated(self) -> Self {
+        match self {
+            NiriTransform::Normal => NiriTransform::Rotate90,
+            NiriTransform::Rotate90 => NiriTransform::Rotate180,
+            NiriTransform::Rotate180 => NiriTransform::Rotate270,
+            NiriTransform::Rotate270 => NiriTransform::Normal,
+            NiriTransform::Flipped => NiriTransform::Flipped90,
+            NiriTransform::Flipped90 => NiriTransform::Flipped180,
+            NiriTransform::Flipped180 => NiriTransform::Flipped270,
+            NiriTransform::Flipped270 => NiriTransform::Flipped,
+        }
+    }
+
+    /// Same rotation with the flip toggled.
+    pub fn flipped(self) -> Self {
+        match self {
+            NiriTransform::Normal => NiriTransform::Flipped,
+            NiriTransform::Rotate90 => NiriTransform::Flipped90,
+            NiriTransform::Rotate180 => NiriTransform::Flipped180,
+            NiriTransform::Rotate270 => NiriTransform::Flipped270,
+            NiriTransform::Flipped => NiriTransform::Normal,
+            NiriTransform::Flipped90 => NiriTransform::Rotate90,
+            NiriTransform::Flipped180 => NiriTransform::Rotate180,
+            NiriTransform::Flipped270 => NiriTransform::Rotate270,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            NiriTransform::Normal => "normal",
+            NiriTransform::Rotate90 => "90",
+            NiriTransform::Rotate180 => "180",
+            NiriTransform::Rotate270 => "270",
+            NiriTransform::Flipped => "flipped",
+            NiriTransform::Flipped90 => "flipped-90",
+            NiriTransform::Flipped180 => "flipped-180",
+            NiriTransform::Flipped270 => "flipped-270",
+        }
+    }
+
+    pub fn swaps_axes(self) -> bool {
+        matches!(
+            self,
+            NiriTransform::Rotate90
+                | NiriTransform::Rotate270
+                | NiriTransform::Flipped90
+                | NiriTransform::Flipped270
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct NiriMode {
+    pub width: i32,
+    pub height: i32,
+    /// Refresh rate in millihertz, as reported by niri.
+    pub refresh_rate: u32,
+    #[serde(default)]
+    pub is_preferred: bool,
+}
+
+impl NiriMode {
+    pub fn refresh_hz(&self) -> f64 {
+        f64::from(self.refresh_rate) / 1000.0
+    }
+
+    pub fn label(&self) -> String {
+        format!("{}x{}@{:.3}", self.width, self.height, self.refresh_hz())
+    }
 }
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct NiriOutput {
     pub name: String,
-    pub logical: NiriLogical,
+    #[serde(default)]
+    pub make: String,
+    #[serde(default)]
+    pub model: String,
+    #[serde(default)]
+    pub serial: Option<String>,
+    /// Physical size in millimetres.
+    #[serde(default)]
+    pub physical_size: Option<(u32, u32)>,
+    #[serde(default)]
+    pub modes: Vec<NiriMode>,
+    #[serde(default)]
+    pub current_mode: Option<usize>,
+    #[serde(default)]
+    pub vrr_supported: bool,
+    #[serde(default)]
+    pub vrr_enabled: bool,
+    pub logical: Option<NiriLogical>,
 }
 
-impl NiriAdapter {
-    pub fn list_outputs(&self) -> anyhow::Result<Vec<String>> {
-        let output = Command::new("niri")
-            .args(["msg", "outputs"])
-            .output()
-            .context("failed to run `niri msg outputs`")?;
-
-        if !output.status.success() {
-            return Err(anyhow!("`niri msg outputs` exited with non-zero status"));
+const INTERNAL_CONNECTORS: [&str; 3] = ["EDP", "LVDS", "DSI"];
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct NiriWorkspace {
+    pub id: u64,
+    pub idx: u8,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub output: Option<String>,
+    #[serde(default)]
+    pub is_focused: bool,
+    #[serde(default)]
+    pub active_window_id: Option<u64>,
+}
+
+impl NiriWorkspace {
+    pub fn label(&self) -> String {
+        match &self.name {
+            Some(name) => name.clone(),
+            None => format!("#{}", self.idx),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct NiriWindow {
+    pub id: u64,
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub app_id: Option<String>,
+    #[serde(default)]
+    pub workspace_id: Option<u64>,
+    #[serde(default)]
+    pub is_focused: bool,
+}
+
+impl NiriWindow {
+    pub fn label(&self) -> String {
+        let title = self.title.as_deref().unwrap_or("(untitled)");
+        match &self.app_id {
+            Some(app_id) => format!("{title} [{app_id}]"),
+            None => title.to_string(),
         }
+    }
+}
 
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        let lines = stdout
-            .lines()
-            .map(str::trim)
-            .filter(|line| !line.is_empty())
-            .map(str::to_string)
-            .collect::<Vec<_>>();
+/// Laptop panels use eDP/LVDS/DSI connectors; everything else (HDMI,
+/// DisplayPort, USB-C alt mode, docks) counts as an external output.
+pub fn is_internal_connector(name: &str) -> bool {
+    let upper = name.to_ascii_uppercase();
+    INTERNAL_CONNECTORS
+        .iter()
+        .any(|prefix| upper.starts_with(prefix))
+}
 
-        Ok(lines)
+impl NiriOutput {
+    pub fn is_internal(&self) -> bool {
+        is_internal_connector(&self.name)
     }
 
-    pub fn list_hdmi_outputs(&self) -> anyhow::Result<Vec<String>> {
-        let outputs = self.list_outputs()?;
-        Ok(outputs
-            .into_iter()
-            .filter(|line| line.to_ascii_uppercase().contains("HDMI"))
-            .collect())
+    pub fn summary_line(&self) -> String {
+        match &self.logical {
+            Some(logical) => format!(
+                "{}: {}x{} at {},{}",
+                self.name, logical.width, logical.height, logical.x, logical.y
+            ),
+            None => format!("{}: disabled", self.name),
+        }
     }
 
-    pub fn connected_output_names(&self) -> anyhow::Result<Vec<String>> {
-        let lines = self.list_outputs()?;
-        let mut names = Vec::new();
-
-        for line in lines {
-            if !line.starts_with("Output ") {
-                continue;
-            }
-            if let (Some(open), Some(close)) = (line.rfind('('), line.rfind(')')) {
-                if close > open + 1 {
-                    names.push(line[open + 1..close].to_string());
-                }
-            }
+    pub fn description(&self) -> String {
+        let mut text = format!("{} {}", self.make, self.model).trim().to_string();
+        if let Some(serial) = &self.serial {
+            text.push_str(&format!(" ({serial})"));
         }
+        if text.is_empty() {
+            text.push_str("unknown display");
+        }
+        text
+    }
 
-        Ok(names)
+    pub fn current_mode(&self) -> Option<&NiriMode> {
+        self.current_mode.and_then(|idx| self.modes.get(idx))
     }
 
-    pub fn outputs_json(&self) -> anyhow::Result<Vec<NiriOutput>> {
-        let output = Command::new("niri")
-            .args(["msg", "-j", "outputs"])
-            .output()
-            .context("failed to run `niri msg -j outputs`")?;
+    pub fn preferred_mode(&self) -> Option<&NiriMode> {
+        self.modes
+            .iter()
+            .find(|mode| mode.is_preferred)
+            .or_else(|| self.modes.first())
+    }
+
+    /// Logical size the output occupies in the layout. Disabled outputs have
+    /// no logical geometry, so the size they will get once turned on is
+    /// derived from their current (or preferred) mode at scale 1.
+    pub fn logical_size(&self) -> Option<(i32, i32)> {
+        if let Some(logical) = &self.logical {
+            return Some((logical.width, logical.height));
+        }
+        let mode = self.current_mode().or_else(|| self.preferred_mode())?;
+        Some(logical_size_for(mode, 1.0, NiriTransform::Normal))
+    }
+}
+
+pub fn logical_size_for(mode: &NiriMode, scale: f64, transform: NiriTransform) -> (i32, i32) {
+    let scale = if scale > 0.0 { scale } else { 1.0 };
+    let width = (f64::from(mode.width) / scale).round() as i32;
+    let height = (f64::from(mode.height) / scale).round() as i32;
+    if transform.swaps_axes() {
+        (height, width)
+    } else {
+        (width, height)
+    }
+}
+
+impl NiriAdapter {
+    pub fn from_env() -> Self {
+        Self {
+            socket: NiriSocket::from_env(),
+        }
+    }
 
-        if !output.status.success() {
-            return Err(anyhow!("`niri msg -j outputs` exited with non-zero status"));
+    #[cfg(test)]
+    pub fn with_socket(path: impl Into<std::path::PathBuf>) -> Self {
+        Self {
+            socket: NiriSocket::at(path),
         }
+    }
+
+    pub fn subscribe_events(&self) -> anyhow::Result<Receiver<NiriEvent>> {
+        let mut stream = self.socket.event_stream()?;
+        let (tx, rx) = mpsc::channel();
 
-        let stdout = String::from_utf8_lossy(&output.stdout);
+        thread::Builder::new()
+            .name("niri-events".to_string())
+            .spawn(move || loop {
+                match stream.next_event() {
+                    Ok(Some(event)) => {
+                        if tx.send(event).is_err() {
+                            return;
+                        }
+                    }
+                    Ok(None) => {
+                        let _ = tx.send(NiriEvent::StreamClosed("niri closed the socket".into()));
+                        return;
+                    }
+                    Err(err) => {
+                        let _ = tx.send(NiriEvent::StreamClosed(err.to_string()));
+                        return;
+                    }
+                }
+            })?;
+
+        Ok(rx)
+    }
+
+    pub fn connected_output_names(&self) -> anyhow::Result<Vec<String>> {
+        Ok(self.outputs_json()?.into_iter().map(|o| o.name).collect())
+    }
+
+    pub fn outputs_json(&self) -> anyhow::Result<Vec<NiriOutput>> {
         let parsed: BTreeMap<String, NiriOutput> =
-            serde_json::from_str(&stdout).context("failed to parse niri outputs json")?;
+            self.socket.send_expecting(&Request::Outputs, "Outputs")?;
         Ok(parsed.into_values().collect())
     }
 
-    pub fn list_hdmi_names(&self) -> anyhow::Result<Vec<String>> {
+    pub fn list_external_names(&self) -> anyhow::Result<Vec<String>> {
         Ok(self
             .outputs_json()?
             .into_iter()
-            .filter(|o| o.name.to_ascii_uppercase().contains("HDMI"))
+            .filter(|o| !o.is_internal())
             .map(|o| o.name)
             .collect())
     }
 
-    pub fn list_non_hdmi_names(&self) -> anyhow::Result<Vec<String>> {
-        Ok(self
-            .outputs_json()?
-            .into_iter()
-            .filter(|o| !o.name.to_ascii_uppercase().contains("HDMI"))
-            .map(|o| o.name)
-            .collect())
+    pub fn output_on(&self, output_name: &str) -> anyhow::Result<()> {
+        self.output_action(output_name, OutputAction::On)
     }
 
-    pub fn output_on(&self, output_name: &str) -> anyhow::Result<()> {
-        let status = Command::new("niri")
-            .args(["msg", "output", output_name, "on"])
-            .status()
-            .context("failed to run `niri msg output <name> on`")?;
-        if !status.success() {
-            return Err(anyhow!("failed to turn on output {output_name}"));
+    pub fn output_off(&self, output_name: &str) -> anyhow::Result<()> {
+        self.output_action(output_name, OutputAction::Off)
+    }
+
+    pub fn set_mode(&self, output_name: &str, mode: &NiriMode) -> anyhow::Result<()> {
+        let width = u16::try_from(mode.width)?;
+        let height = u16::try_from(mode.height)?;
+        self.output_action(
+            output_name,
+            OutputAction::Mode {
+                mode: ModeToSet::Specific {
+                    width,
+                    height,
+                    refresh: Some(mode.refresh_hz()),
+                },
+            },
+        )
+    }
+
+    pub fn set_scale(&self, output_name: &str, scale: f64) -> anyhow::Result<()> {
+        if !(scale.is_finite() && scale > 0.0) {
+            return Err(anyhow::anyhow!("invalid scale {scale} for output {output_name}"));
         }
+        self.output_action(
+            output_name,
+            OutputAction::Scale {
+                scale: ScaleToSet::Specific(scale),
+            },
+        )
+    }
+
+    pub fn set_transform(&self, output_name: &str, transform: NiriTransform) -> anyhow::Result<()> {
+        self.output_action(output_name, OutputAction::Transform { transform })
+    }
+
+    pub fn set_position(&self, output_name: &str, x: i32, y: i32) -> anyhow::Result<()> {
+        self.output_action(
+            output_name,
+            OutputAction::Position {
+                position: PositionToSet::Specific { x, y },
+            },
+        )
+    }
+
+    pub fn set_position_auto(&self, output_name: &str) -> anyhow::Result<()> {
+        self.output_action(
+            output_name,
+            OutputAction::Position {
+                position: PositionToSet::Automatic,
+            },
+        )
+    }
+
+    pub fn workspaces(&self) -> anyhow::Result<Vec<NiriWorkspace>> {
+        Ok(self
+            .socket
+            .send_expecting(&Request::Workspaces, "Workspaces")?)
+    }
+
+    pub fn move_workspace_to_monitor(&self, workspace_id: u64, output: &str) -> anyhow::Result<()> {
+        self.socket.send_action(Action::MoveWorkspaceToMonitor {
+            output: output.to_string(),
+            reference: Some(WorkspaceReferenceArg::Id(workspace_id)),
+        })?;
         Ok(())
     }
 
-    pub fn output_off(&self, output_name: &str) -> anyhow::Result<()> {
-        let status = Command::new("niri")
-            .args(["msg", "output", output_name, "off"])
-            .status()
-            .context("failed to run `niri msg output <name> off`")?;
-        if !status.success() {
-            return Err(anyhow!("failed to turn off output {output_name}"));
-        }
+    pub fn windows(&self) -> anyhow::Result<Vec<NiriWindow>> {
+        Ok(self.socket.send_expecting(&Request::Windows, "Windows")?)
+    }
+
+    pub fn move_window_to_monitor(&self, window_id: u64, output: &str) -> anyhow::Result<()> {
+        self.socket.send_action(Action::MoveWindowToMonitor {
+            id: Some(window_id),
+            output: output.to_string(),
+        })?;
         Ok(())
     }
 
-    pub fn set_position(&self, output_name: &str, x: i32, y: i32) -> anyhow::Result<()> {
-        let status = Command::new("niri")
-            .args([
-                "msg",
-                "output",
-                output_name,
-                "position",
-                "set",
-                &x.to_string(),
-                &y.to_string(),
-            ])
-            .status()
-            .context("failed to run `niri msg output <name> position set <x> <y>`")?;
-        if !status.success() {
-            return Err(anyhow!(
-                "failed to set position for output {output_name} to {x},{y}"
-            ));
-        }
+    /// niri only offers a toggle, so this un-fullscreens a window that
+    /// already is fullscreen.
+    pub fn toggle_fullscreen_window(&self, window_id: u64) -> anyhow::Result<()> {
+        self.socket
+            .send_action(Action::FullscreenWindow { id: Some(window_id) })?;
         Ok(())
     }
 
-    pub fn set_position_auto(&self, output_name: &str) -> anyhow::Result<()> {
-        let status = Command::new("niri")
-            .args(["msg", "output", output_name, "position", "auto"])
-            .status()
-            .context("failed to run `niri msg output <name> position auto`")?;
-        if !status.success() {
-            return Err(anyhow!("failed to set auto position for output {output_name}"));
+    pub fn set_dynamic_cast_window(&self, window_id: u64) -> anyhow::Result<()> {
+        self.socket
+            .send_action(Action::SetDynamicCastWindow { id: Some(window_id) })?;
+        Ok(())
+    }
+
+    pub fn set_dynamic_cast_monitor(&self, output: &str) -> anyhow::Result<()> {
+        self.socket.send_action(Action::SetDynamicCastMonitor {
+            output: Some(output.to_string()),
+        })?;
+        Ok(())
+    }
+
+    pub fn clear_dynamic_cast_target(&self) -> anyhow::Result<()> {
+        self.socket.send_action(Action::ClearDynamicCastTarget {})?;
+        Ok(())
+    }
+
+    fn output_action(&self, output_name: &str, action: OutputAction) -> anyhow::Result<()> {
+        let request = Request::Output {
+            output: output_name.to_string(),
+            action,
+        };
+        let changed: String = self.socket.send_expecting(&request, "OutputConfigChanged")?;
+        if changed == "OutputWasMissing" {
+            return Err(NiriIpcError::OutputMissing(output_name.to_string()).into());
         }
         Ok(())
     }
 }
+
+#[cfg(test)]
+pub mod fixtures {
+    use super::{NiriLogical, NiriMode, NiriOutput, NiriTransform};
+
+    /// An output with a single 1080p mode; `logical` is `(x, y, width,
+    /// height)` when it is on.
+    pub fn output(name: &str, logical: Option<(i32, i32, i32, i32)>) -> NiriOutput {
+        NiriOutput {
+            name: name.to_string(),
+            make: String::new(),
+            model: String::new(),
+            serial: None,
+            physical_size: None,
+            modes: vec![NiriMode {
+                width: 1920,
+                height: 1080,
+                refresh_rate: 60000,
+                is_preferred: true,
+            }],
+            current_mode: Some(0),
+            vrr_supported: false,
+            vrr_enabled: false,
+            logical: logical.map(|(x, y, width, height)| NiriLogical {
+                x,
+                y,
+                width,
+                height,
+                scale: 1.0,
+                transform: NiriTransform::Normal,
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{logical_size_for, NiriAdapter, NiriMode, NiriTransform};
+    use crate::adapters::niri_ipc::{stub::StubNiri, NiriIpcError};
+
+    const OUTPUTS_REPLY: &str = r#"{"Ok":{"Outputs":{
+        "eDP-1":{"name":"eDP-1","make":"BOE","model":"0x0BCA","serial":null,
+            "physical_size":[300,190],
+            "modes":[{"width":2880,"height":1800,"refresh_rate":90000,"is_preferred":true}],
+            "current_mode":0,"vrr_supported":true,"vrr_enabled":false,
+            "logical":{"x":0,"y":0,"width":1920,"height":1200,"scale":1.5,"transform":"Normal"}},
+        "HDMI-A-1":{"name":"HDMI-A-1","make":"LG Electronics","model":"LG TV SSCR2","serial":"0x01010101",
+            "physical_size":[1600,900],
+            "modes":[
+                {"width":3840,"height":2160,"refresh_rate":30000,"is_preferred":false},
+                {"width":1920,"height":1080,"refresh_rate":60000,"is_preferred":true}
+            ],
+            "current_mode":null,"vrr_supported":false,"vrr_enabled":false,"logical":null}
+    }}}"#;
+
+    fn one_line(json: &str) -> String {
+        json.lines().map(str::trim).collect()
+    }
+
+    #[test]
+    fn reads_outputs_over_socket() {
+        let stub = StubNiri::serve(&[&one_line(OUTPUTS_REPLY)]);
+        let niri = NiriAdapter::with_socket(&stub.path);
+
+        let outputs = niri.outputs_json().unwrap();
+        assert_eq!(outputs.len(), 2);
+        assert_eq!(outputs[0].name, "HDMI-A-1");
+        assert!(outputs[0].logical.is_none());
+        assert_eq!(outputs[1].logical.as_ref().unwrap().width, 1920);
+
+        assert_eq!(stub.requests(), vec!["\"Outputs\"".to_string()]);
+    }
+
+    #[test]
+    fn parses_full_output_model() {
+        let stub = StubNiri::serve(&[&one_line(OUTPUTS_REPLY)]);
+        let outputs = NiriAdapter::with_socket(&stub.path).outputs_json().unwrap();
+        stub.requests();
+
+        let tv = &outputs[0];
+        assert_eq!(tv.description(), "LG Electronics LG TV SSCR2 (0x01010101)");
+        assert_eq!(tv.physical_size, Some((1600, 900)));
+        assert_eq!(tv.modes.len(), 2);
+        assert!(tv.current_mode().is_none());
+        assert_eq!(tv.preferred_mode().unwrap().label(), "1920x1080@60.000");
+        assert_eq!(tv.logical_size(), Some((1920, 1080)));
+
+        let panel = &outputs[1];
+        assert!(panel.vrr_supported && !panel.vrr_enabled);
+        assert_eq!(panel.current_mode().unwrap().refresh_hz(), 90.0);
+        let logical = panel.logical.as_ref().unwrap();
+        assert_eq!(logical.scale, 1.5);
+        assert_eq!(logical.transform, NiriTransform::Normal);
+    }
+
+    #[test]
+    fn derives_logical_size_from_mode() {
+        let mode = NiriMode {
+            width: 3840,
+            height: 2160,
+            refresh_rate: 60000,
+            is_preferred: true,
+        };
+        assert_eq!(logical_size_for(&mode, 2.0, NiriTransform::Normal), (1920, 1080));
+        assert_eq!(logical_size_for(&mode, 1.5, NiriTransform::Rotate90), (1440, 2560));
+        assert_eq!(
+            serde_json::from_str::<NiriTransform>("\"Flipped270\"").unwrap(),
+            NiriTransform::Flipped270
+        );
+        // Snapshots written before the IPC names were fixed.
+        assert_eq!(
+            serde_json::from_str::<NiriTransform>("\"flipped-270\"").unwrap(),
+            NiriTransform::Flipped270
+        );
+    }
+
+    #[test]
+    fn cycles_transforms() {
+        assert_eq!(NiriTransform::Rotate270.rotated(), NiriTransform::Normal);
+        assert_eq!(NiriTransform::Flipped.rotated(), NiriTransform::Flipped90);
+        assert_eq!(NiriTransform::Rotate180.flipped(), NiriTransform::Flipped180);
+        assert_eq!(NiriTransform::from_name("FLIPPED-90"), Some(NiriTransform::Flipped90));
+        assert_eq!(NiriTransform::from_name("sideways"), None);
+    }
+
+    #[test]
+    fn sends_output_actions() {
+        let applied = r#"{"Ok":{"OutputConfigChanged":"Applied"}}"#;
+        let stub = StubNiri::serve(&[applied, applied, applied, applied, applied, applied]);
+        let niri = NiriAdapter::with_socket(&stub.path);
+
+        niri.output_on("HDMI-A-1").unwrap();
+        niri.set_position("HDMI-A-1", -1920, 0).unwrap();
+        niri.set_mode(
+            "HDMI-A-1",
+            &NiriMode {
+                width: 1920,
+                height: 1080,
+                refresh_rate: 60000,
+                is_preferred: true,
+            },
+        )
+        .unwrap();
+        niri.set_scale("HDMI-A-1", 2.0).unwrap();
+        niri.set_transform("HDMI-A-1", NiriTransform::Flipped90).unwrap();
+        niri.set_transform("HDMI-A-1", NiriTransform::Rotate270).unwrap();
+        assert!(niri.set_scale("HDMI-A-1", 0.0).is_err());
+
+        assert_eq!(
+            stub.requests(),
+            vec![
+                r#"{"Output":{"output":"HDMI-A-1","action":"On"}}"#.to_string(),
+                r#"{"Output":{"output":"HDMI-A-1","action":{"Position":{"position":{"Specific":{"x":-1920,"y":0}}}}}}"#.to_string(),
+                r#"{"Output":{"output":"HDMI-A-1","action":{"Mode":{"mode":{"Specific":{"width":1920,"height":1080,"refresh":60.0}}}}}}"#.to_string(),
+                r#"{"Output":{"output":"HDMI-A-1","action":{"Scale":{"scale":{"Specific":2.0}}}}}"#.to_string(),
+                r#"{"Output":{"output":"HDMI-A-1","action":{"Transform":{"transform":"Flipped90"}}}}"#.to_string(),
+                r#"{"Output":{"output":"HDMI-A-1","action":{"Transform":{"transform":"270"}}}}"#.to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn sends_window_to_monitor() {
+        let windows = r#"{"Ok":{"Windows":[{"id":7,"title":"Movie","app_id":"mpv","pid":42,"workspace_id":2,"is_focused":true,"is_floating":false}]}}"#;
+        let handled = r#"{"Ok":"Handled"}"#;
+        let stub = StubNiri::serve(&[windows, handled, handled]);
+        let niri = NiriAdapter::with_socket(&stub.path);
+
+        let listed = niri.windows().unwrap();
+        assert_eq!(listed[0].label(), "Movie [mpv]");
+        niri.move_window_to_monitor(listed[0].id, "HDMI-A-1").unwrap();
+        niri.toggle_fullscreen_window(listed[0].id).unwrap();
+
+        let requests = stub.requests();
+        assert_eq!(
+            requests[1],
+            r#"{"Action":{"MoveWindowToMonitor":{"id":7,"output":"HDMI-A-1"}}}"#
+        );
+        assert_eq!(requests[2], r#"{"Action":{"FullscreenWindow":{"id":7}}}"#);
+    }
+
+    #[test]
+    fn sends_dynamic_cast_actions() {
+        let handled = r#"{"Ok":"Handled"}"#;
+        let stub = StubNiri::serve(&[handled, handled, handled]);
+        let niri = NiriAdapter::with_socket(&stub.path);
+
+        niri.set_dynamic_cast_window(7).unwrap();
+        niri.set_dynamic_cast_monitor("HDMI-A-1").unwrap();
+        niri.clear_dynamic_cast_target().unwrap();
+
+        assert_eq!(
+            stub.requests(),
+            vec![
+                r#"{"Action":{"SetDynamicCastWindow":{"id":7}}}"#.to_string(),
+                r#"{"Action":{"SetDynamicCastMonitor":{"output":"HDMI-A-1"}}}"#.to_string(),
+                r#"{"Action":{"ClearDynamicCastTarget":{}}}"#.to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_typed_errors() {
+        let stub = StubNiri::serve(&[
+            r#"{"Ok":{"OutputConfigChanged":"OutputWasMissing"}}"#,
+            r#"{"Err":"error parsing request"}"#,
+        ]);
+        let niri = NiriAdapter::with_socket(&stub.path);
+
+        let missing = niri.output_off("DP-9").unwrap_err();
+        assert!(matches!(
+            missing.downcast_ref::<NiriIpcError>(),
+            Some(NiriIpcError::OutputMissing(name)) if name == "DP-9"
+        ));
+
+        let rejected = niri.output_on("DP-9").unwrap_err();
+        assert!(matches!(
+            rejected.downcast_ref::<NiriIpcError>(),
+            Some(NiriIpcError::Rejected(_))
+        ));
+        stub.requests();
+    }
+
+    #[test]
+    fn missing_socket_is_reported() {
+        let niri = NiriAdapter::with_socket("/nonexistent/niri.sock");
+        let err = niri.outputs_json().unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<NiriIpcError>(),
+            Some(NiriIpcError::Connect { .. })
+        ));
+    }
+}
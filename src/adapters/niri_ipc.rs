@@ -0,0 +1,455 @@
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Write};
+use std::os::unix::net::UnixStream;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::adapters::niri::NiriTransform;
+
+const SOCKET_ENV: &str = "NIRI_SOCKET";
+const IO_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Requests understood by niri's IPC socket. Serialized exactly like
+/// `niri_ipc::Request`, one JSON document per line.
+#[derive(Debug, Clone, Serialize)]
+pub enum Request {
+    Outputs,
+    Workspaces,
+    Windows,
+    Output {
+        output: String,
+        action: OutputAction,
+    },
+    Action(Action),
+    EventStream,
+}
+
+/// The subset of `niri_ipc::Action` niri-cast sends.
+#[derive(Debug, Clone, Serialize)]
+pub enum Action {
+    MoveWorkspaceToMonitor {
+        output: String,
+        reference: Option<WorkspaceReferenceArg>,
+    },
+    MoveWindowToMonitor {
+        id: Option<u64>,
+        output: String,
+    },
+    FullscreenWindow {
+        id: Option<u64>,
+    },
+    SetDynamicCastWindow {
+        id: Option<u64>,
+    },
+    SetDynamicCastMonitor {
+        output: Option<String>,
+    },
+    ClearDynamicCastTarget {},
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub enum WorkspaceReferenceArg {
+    Id(u64),
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub enum OutputAction {
+    On,
+    Off,
+    Mode { mode: ModeToSet },
+    Scale { scale: ScaleToSet },
+    Transform { transform: NiriTransform },
+    Position { position: PositionToSet },
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub enum ScaleToSet {
+    Specific(f64),
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub enum ModeToSet {
+    Specific {
+        width: u16,
+        height: u16,
+        refresh: Option<f64>,
+    },
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub enum PositionToSet {
+    Automatic,
+    Specific { x: i32, y: i32 },
+}
+
+/// Events read from an `EventStream` connection. Only the events that can
+/// signal an output change are decoded; everything else is kept by name.
+/// A line that is not JSON is handed on as `Undecodable` so one bad line
+/// does not end the stream.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NiriEvent {
+    WorkspacesChanged,
+    ConfigLoaded,
+    WindowsChanged,
+    Other(String),
+    Undecodable { line: String, error: String },
+    StreamClosed(String),
+}
+
+impl NiriEvent {
+    fn from_line(line: &str) -> Self {
+        let line = line.trim();
+        let value: Value = match serde_json::from_str(line) {
+            Ok(value) => value,
+            Err(err) => {
+                return NiriEvent::Undecodable {
+                    line: line.to_string(),
+                    error: err.to_string(),
+                }
+            }
+        };
+        let kind = match &value {
+            Value::Object(map) => map.keys().next().cloned().unwrap_or_default(),
+            Value::String(name) => name.clone(),
+            other => other.to_string(),
+        };
+        match kind.as_str() {
+            "WorkspacesChanged" => NiriEvent::WorkspacesChanged,
+            "ConfigLoaded" => NiriEvent::ConfigLoaded,
+            "WindowsChanged" | "WindowOpenedOrChanged" | "WindowClosed" => {
+                NiriEvent::WindowsChanged
+            }
+            _ => NiriEvent::Other(kind),
+        }
+    }
+
+    pub fn may_change_outputs(&self) -> bool {
+        matches!(self, NiriEvent::WorkspacesChanged | NiriEvent::ConfigLoaded)
+    }
+}
+
+pub struct NiriEventStream {
+    reader: BufReader<UnixStream>,
+}
+
+impl NiriEventStream {
+    pub fn next_event(&mut self) -> Result<Option<NiriEvent>, NiriIpcError> {
+        let mut line = String::new();
+        if self.reader.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        Ok(Some(NiriEvent::from_line(&line)))
+    }
+}
+
+#[derive(Debug)]
+pub enum NiriIpcError {
+    SocketUnset,
+    Connect { path: PathBuf, source: io::Error },
+    Io(io::Error),
+    Decode(serde_json::Error),
+    Rejected(String),
+    UnexpectedReply { expected: &'static str, got: String },
+    OutputMissing(String),
+}
+
+impl fmt::Display for NiriIpcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NiriIpcError::SocketUnset => {
+                write!(
+                    f,
+                    "{SOCKET_ENV} is not set (is niri running in this session?)"
+                )
+            }
+            NiriIpcError::Connect { path, source } => {
+                write!(
+                    f,
+                    "failed to connect to niri socket {}: {source}",
+                    path.display()
+                )
+            }
+            NiriIpcError::Io(err) => write!(f, "niri socket i/o failed: {err}"),
+            NiriIpcError::Decode(err) => write!(f, "failed to decode niri reply: {err}"),
+            NiriIpcError::Rejected(msg) => write!(f, "niri rejected request: {msg}"),
+            NiriIpcError::UnexpectedReply { expected, got } => {
+                write!(f, "expected {expected} reply from niri, got {got}")
+            }
+            NiriIpcError::OutputMissing(name) => write!(f, "output {name} is not connected"),
+        }
+    }
+}
+
+impl std::error::Error for NiriIpcError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            NiriIpcError::Connect { source, .. } => Some(source),
+            NiriIpcError::Io(err) => Some(err),
+            NiriIpcError::Decode(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for NiriIpcError {
+    fn from(err: io::Error) -> Self {
+        NiriIpcError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for NiriIpcError {
+    fn from(err: serde_json::Error) -> Self {
+        NiriIpcError::Decode(err)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct NiriSocket {
+    path: Option<PathBuf>,
+}
+
+impl NiriSocket {
+    pub fn from_env() -> Self {
+        Self {
+            path: std::env::var_os(SOCKET_ENV).map(PathBuf::from),
+        }
+    }
+
+    #[cfg(test)]
+    pub fn at(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: Some(path.into()),
+        }
+    }
+
+    pub fn connect(&self) -> Result<UnixStream, NiriIpcError> {
+        let path = self.path.as_ref().ok_or(NiriIpcError::SocketUnset)?;
+        let stream = UnixStream::connect(path).map_err(|source| NiriIpcError::Connect {
+            path: path.clone(),
+            source,
+        })?;
+        stream.set_read_timeout(Some(IO_TIMEOUT))?;
+        stream.set_write_timeout(Some(IO_TIMEOUT))?;
+        Ok(stream)
+    }
+
+    /// Sends one request on a fresh connection and returns the `Ok` payload.
+    pub fn send(&self, request: &Request) -> Result<Value, NiriIpcError> {
+        let mut stream = self.connect()?;
+        write_request(&mut stream, request)?;
+        let mut reader = BufReader::new(stream);
+        read_reply(&mut reader)
+    }
+
+    /// Sends a request whose reply is a single-field response such as
+    /// `{"Outputs": {...}}` and decodes the field.
+    pub fn send_expecting<T: DeserializeOwned>(
+        &self,
+        request: &Request,
+        variant: &'static str,
+    ) -> Result<T, NiriIpcError> {
+        let payload = self.send(request)?;
+        take_variant(payload, variant)
+    }
+
+    /// Runs a compositor action; niri answers a bare `"Handled"`.
+    pub fn send_action(&self, action: Action) -> Result<(), NiriIpcError> {
+        let reply = self.send(&Request::Action(action))?;
+        if reply != Value::String("Handled".to_string()) {
+            return Err(NiriIpcError::UnexpectedReply {
+                expected: "Handled",
+                got: reply.to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Switches a fresh connection into event-stream mode. niri answers the
+    /// request once and then writes one event per line until disconnected.
+    pub fn event_stream(&self) -> Result<NiriEventStream, NiriIpcError> {
+        let mut stream = self.connect()?;
+        write_request(&mut stream, &Request::EventStream)?;
+        let mut reader = BufReader::new(stream);
+        let reply = read_reply(&mut reader)?;
+        if reply != Value::String("Handled".to_string()) {
+            return Err(NiriIpcError::UnexpectedReply {
+                expected: "Handled",
+                got: reply.to_string(),
+            });
+        }
+        reader.get_ref().set_read_timeout(None)?;
+        Ok(NiriEventStream { reader })
+    }
+}
+
+fn write_request(stream: &mut UnixStream, request: &Request) -> Result<(), NiriIpcError> {
+    let mut line = serde_json::to_string(request)?;
+    line.push('\n');
+    stream.write_all(line.as_bytes())?;
+    stream.flush()?;
+    Ok(())
+}
+
+fn read_reply(reader: &mut impl BufRead) -> Result<Value, NiriIpcError> {
+    let mut line = String::new();
+    if reader.read_line(&mut line)? == 0 {
+        return Err(NiriIpcError::Io(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "niri closed the socket without replying",
+        )));
+    }
+    decode_reply(&line)
+}
+
+fn decode_reply(line: &str) -> Result<Value, NiriIpcError> {
+    let reply: Result<Value, String> = serde_json::from_str(line.trim())?;
+    reply.map_err(NiriIpcError::Rejected)
+}
+
+fn take_variant<T: DeserializeOwned>(
+    payload: Value,
+    variant: &'static str,
+) -> Result<T, NiriIpcError> {
+    match payload {
+        Value::Object(mut map) if map.contains_key(variant) => {
+            let inner = map.remove(variant).unwrap_or(Value::Null);
+            Ok(serde_json::from_value(inner)?)
+        }
+        other => Err(NiriIpcError::UnexpectedReply {
+            expected: variant,
+            got: other.to_string(),
+        }),
+    }
+}
+
+#[cfg(test)]
+pub mod stub {
+    use std::io::{BufRead, BufReader, Write};
+    use std::os::unix::net::UnixListener;
+    use std::path::PathBuf;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::thread::JoinHandle;
+
+    static NEXT_SOCKET: AtomicUsize = AtomicUsize::new(0);
+
+    /// Stand-in for niri's socket: answers each connection with the next
+    /// canned reply line and records the request it received. A reply may
+    /// span several lines to emulate an event stream.
+    pub struct StubNiri {
+        pub path: PathBuf,
+        handle: JoinHandle<Vec<String>>,
+    }
+
+    impl StubNiri {
+        pub fn serve(replies: &[&str]) -> Self {
+            let path = std::env::temp_dir().join(format!(
+                "niri-cast-test-{}-{}.sock",
+                std::process::id(),
+                NEXT_SOCKET.fetch_add(1, Ordering::SeqCst)
+            ));
+            let _ = std::fs::remove_file(&path);
+            let listener = UnixListener::bind(&path).expect("bind stub niri socket");
+            let replies = replies.iter().map(|r| r.to_string()).collect::<Vec<_>>();
+
+            let handle = std::thread::spawn(move || {
+                let mut requests = Vec::new();
+                for reply in replies {
+                    let (stream, _) = listener.accept().expect("accept stub connection");
+                    let mut reader = BufReader::new(stream);
+                    let mut line = String::new();
+                    reader.read_line(&mut line).expect("read stub request");
+                    requests.push(line.trim().to_string());
+                    let mut stream = reader.into_inner();
+                    for reply_line in reply.lines() {
+                        writeln!(stream, "{reply_line}").expect("write stub reply");
+                    }
+                }
+                requests
+            });
+
+            Self { path, handle }
+        }
+
+        /// Waits for all canned replies to be consumed and returns the
+        /// requests in the order they arrived.
+        pub fn requests(self) -> Vec<String> {
+            let requests = self.handle.join().expect("stub niri thread panicked");
+            let _ = std::fs::remove_file(&self.path);
+            requests
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        decode_reply, stub::StubNiri, Action, NiriEvent, NiriIpcError, NiriSocket, OutputAction,
+        PositionToSet, Request, WorkspaceReferenceArg,
+    };
+
+    #[test]
+    fn serializes_requests_like_niri_ipc() {
+        assert_eq!(
+            serde_json::to_string(&Request::Outputs).unwrap(),
+            "\"Outputs\""
+        );
+
+        let request = Request::Output {
+            output: "HDMI-A-1".to_string(),
+            action: OutputAction::Position {
+                position: PositionToSet::Specific { x: 1920, y: 0 },
+            },
+        };
+        assert_eq!(
+            serde_json::to_string(&request).unwrap(),
+            r#"{"Output":{"output":"HDMI-A-1","action":{"Position":{"position":{"Specific":{"x":1920,"y":0}}}}}}"#
+        );
+
+        let request = Request::Action(Action::MoveWorkspaceToMonitor {
+            output: "HDMI-A-1".to_string(),
+            reference: Some(WorkspaceReferenceArg::Id(3)),
+        });
+        assert_eq!(
+            serde_json::to_string(&request).unwrap(),
+            r#"{"Action":{"MoveWorkspaceToMonitor":{"output":"HDMI-A-1","reference":{"Id":3}}}}"#
+        );
+    }
+
+    #[test]
+    fn decodes_err_reply_as_rejected() {
+        match decode_reply(r#"{"Err":"unknown output"}"#) {
+            Err(NiriIpcError::Rejected(msg)) => assert_eq!(msg, "unknown output"),
+            other => panic!("unexpected reply: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn reads_event_stream_until_closed() {
+        let stub = StubNiri::serve(&["{\"Ok\":\"Handled\"}\n\
+             {\"WorkspacesChanged\":{\"workspaces\":[]}}\n\
+             {\"WindowFocusChanged\":{\"id\":null}}\n\
+             {\"WindowsChanged\":\n\
+             {\"ConfigLoaded\":{\"failed\":false}}"]);
+        let mut events = NiriSocket::at(&stub.path).event_stream().unwrap();
+
+        assert_eq!(
+            events.next_event().unwrap(),
+            Some(NiriEvent::WorkspacesChanged)
+        );
+        assert_eq!(
+            events.next_event().unwrap(),
+            Some(NiriEvent::Other("WindowFocusChanged".to_string()))
+        );
+        assert!(matches!(
+            events.next_event().unwrap(),
+            Some(NiriEvent::Undecodable { line, .. }) if line == "{\"WindowsChanged\":"
+        ));
+        assert_eq!(events.next_event().unwrap(), Some(NiriEvent::ConfigLoaded));
+        assert_eq!(stub.requests(), vec!["\"EventStream\"".to_string()]);
+        assert_eq!(events.next_event().unwrap(), None);
+    }
+}
@@ -0,0 +1,440 @@
+use std::process::Command;
+
+use anyhow::{anyhow, Context};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+/// The parts of a `pw-dump` snapshot niri-cast cares about: audio nodes
+/// (sinks, sources and streams), the cards behind them, and the links
+/// between nodes.
+#[derive(Debug, Clone, Default)]
+pub struct PwDump {
+    pub nodes: Vec<PwNode>,
+    pub devices: Vec<PwDevice>,
+    pub links: Vec<PwLink>,
+    pub default_sink: Option<String>,
+    pub default_source: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct PwNode {
+    pub id: u32,
+    /// `object.serial`; pipewire-pulse uses it as the sink-input index.
+    pub serial: Option<u64>,
+    pub name: String,
+    pub description: String,
+    pub media_class: String,
+    pub device_id: Option<u32>,
+    /// `card.profile.device`: which route of the card feeds this node.
+    pub profile_device: Option<u32>,
+    pub application: Option<String>,
+    /// `application.process.binary`, e.g. `Discord` for its WebRTC streams.
+    pub binary: Option<String>,
+    /// `media.title`, falling back to `media.name`, e.g. the video playing.
+    pub media_title: Option<String>,
+    /// Volume on wpctl's scale (1.0 = 100%), when the node reports one.
+    pub volume: Option<f32>,
+    pub muted: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct PwDevice {
+    pub id: u32,
+    pub name: String,
+    pub description: String,
+    pub profiles: Vec<PwProfile>,
+    pub active_profile: Option<PwProfile>,
+    pub routes: Vec<PwRoute>,
+    pub active_routes: Vec<PwRoute>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct PwProfile {
+    pub index: u32,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub available: PwAvailability,
+    #[serde(default)]
+    pub priority: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct PwRoute {
+    pub index: u32,
+    pub direction: PwDirection,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub available: PwAvailability,
+    /// Profiles that expose the route (`EnumRoute`, newer releases only).
+    #[serde(default)]
+    pub profiles: Vec<u32>,
+    /// Card device the route is active on (`Route`).
+    #[serde(default)]
+    pub device: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum PwDirection {
+    Input,
+    Output,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PwAvailability {
+    Yes,
+    No,
+    #[default]
+    Unknown,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PwLink {
+    pub output_node: u32,
+    pub input_node: u32,
+}
+
+impl PwNode {
+    pub fn is_sink(&self) -> bool {
+        self.media_class == "Audio/Sink"
+    }
+
+    pub fn is_source(&self) -> bool {
+        self.media_class == "Audio/Source"
+    }
+
+    pub fn is_playback_stream(&self) -> bool {
+        self.media_class == "Stream/Output/Audio"
+    }
+}
+
+impl PwDevice {
+    /// The ports a profile drives, e.g. `hdmi-output-1` for
+    /// `output:hdmi-stereo-extra1`.
+    pub fn routes_for(&self, profile: &PwProfile) -> impl Iterator<Item = &PwRoute> {
+        let index = profile.index;
+        self.routes
+            .iter()
+            .filter(move |route| route.profiles.contains(&index))
+    }
+}
+
+impl PwDump {
+    pub fn capture() -> anyhow::Result<Self> {
+        let output = Command::new("pw-dump")
+            .output()
+            .context("failed to run `pw-dump`")?;
+        if !output.status.success() {
+            return Err(anyhow!("`pw-dump` exited with non-zero status"));
+        }
+        parse_pw_dump(&String::from_utf8_lossy(&output.stdout))
+    }
+
+    pub fn sinks(&self) -> impl Iterator<Item = &PwNode> {
+        self.nodes.iter().filter(|node| node.is_sink())
+    }
+
+    pub fn sources(&self) -> impl Iterator<Item = &PwNode> {
+        self.nodes.iter().filter(|node| node.is_source())
+    }
+
+    pub fn playback_streams(&self) -> impl Iterator<Item = &PwNode> {
+        self.nodes.iter().filter(|node| node.is_playback_stream())
+    }
+
+    pub fn node(&self, id: u32) -> Option<&PwNode> {
+        self.nodes.iter().find(|node| node.id == id)
+    }
+
+    pub fn device(&self, id: u32) -> Option<&PwDevice> {
+        self.devices.iter().find(|device| device.id == id)
+    }
+
+    /// The card route (port) currently feeding a node, e.g. `hdmi-output-1`.
+    pub fn active_route(&self, node: &PwNode) -> Option<&PwRoute> {
+        let device = self.device(node.device_id?)?;
+        let profile_device = node.profile_device?;
+        device
+            .active_routes
+            .iter()
+            .find(|route| route.device == Some(profile_device))
+    }
+
+    /// The sink a playback stream is linked to.
+    pub fn sink_of(&self, stream: &PwNode) -> Option<&PwNode> {
+        self.links
+            .iter()
+            .filter(|link| link.output_node == stream.id)
+            .filter_map(|link| self.node(link.input_node))
+            .find(|node| node.is_sink())
+    }
+}
+
+#[derive(Deserialize)]
+struct RawObject {
+    id: u32,
+    #[serde(rename = "type", default)]
+    kind: String,
+    #[serde(default)]
+    info: Option<RawInfo>,
+    #[serde(default)]
+    props: Map<String, Value>,
+    #[serde(default)]
+    metadata: Option<Vec<RawMetadata>>,
+}
+
+#[derive(Deserialize, Default)]
+struct RawInfo {
+    #[serde(default)]
+    props: Map<String, Value>,
+    #[serde(default)]
+    params: RawParams,
+    #[serde(rename = "output-node-id")]
+    output_node_id: Option<u32>,
+    #[serde(rename = "input-node-id")]
+    input_node_id: Option<u32>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "PascalCase")]
+struct RawParams {
+    #[serde(default)]
+    enum_profile: Vec<PwProfile>,
+    #[serde(default)]
+    profile: Vec<PwProfile>,
+    #[serde(default)]
+    enum_route: Vec<PwRoute>,
+    #[serde(default)]
+    route: Vec<PwRoute>,
+    #[serde(default)]
+    props: Vec<RawProps>,
+}
+
+#[derive(Deserialize)]
+struct RawProps {
+    #[serde(default, rename = "channelVolumes")]
+    channel_volumes: Vec<f32>,
+    #[serde(default)]
+    mute: bool,
+}
+
+#[derive(Deserialize)]
+struct RawMetadata {
+    key: String,
+    #[serde(default)]
+    value: Value,
+}
+
+/// Parses `pw-dump` output. Objects pw-dump reports as removed (`info:
+/// null`) and object types niri-cast does not model are skipped.
+pub fn parse_pw_dump(json: &str) -> anyhow::Result<PwDump> {
+    let objects: Vec<RawObject> =
+        serde_json::from_str(json).context("failed to parse pw-dump output")?;
+    let mut dump = PwDump::default();
+
+    for object in objects {
+        if object.kind == "PipeWire:Interface:Metadata" {
+            if object.props.get("metadata.name").and_then(Value::as_str) == Some("default") {
+                for entry in object.metadata.unwrap_or_default() {
+                    match entry.key.as_str() {
+                        "default.audio.sink" => dump.default_sink = metadata_name(&entry.value),
+                        "default.audio.source" => dump.default_source = metadata_name(&entry.value),
+                        _ => {}
+                    }
+                }
+            }
+            continue;
+        }
+
+        let Some(info) = object.info else {
+            continue;
+        };
+        match object.kind.as_str() {
+            "PipeWire:Interface:Node" => {
+                let props = &info.props;
+                let Some(media_class) = prop_str(props, "media.class") else {
+                    continue;
+                };
+                if !media_class.contains("Audio") {
+                    continue;
+                }
+                let name = prop_str(props, "node.name").unwrap_or_default();
+                let description = prop_str(props, "node.description")
+                    .or_else(|| prop_str(props, "node.nick"))
+                    .or_else(|| prop_str(props, "application.name"))
+                    .unwrap_or_else(|| name.clone());
+                dump.nodes.push(PwNode {
+                    id: object.id,
+                    serial: prop_u64(props, "object.serial"),
+                    name,
+                    description,
+                    media_class,
+                    device_id: prop_u64(props, "device.id").map(|id| id as u32),
+                    profile_device: prop_u64(props, "card.profile.device").map(|id| id as u32),
+                    application: prop_str(props, "application.name"),
+                    binary: prop_str(props, "application.process.binary"),
+                    media_title: prop_str(props, "media.title")
+                        .or_else(|| prop_str(props, "media.name")),
+                    volume: info.params.props.first().and_then(volume_from_props),
+                    muted: info.params.props.first().is_some_and(|props| props.mute),
+                });
+            }
+            "PipeWire:Interface:Device" => {
+                let props = &info.props;
+                if prop_str(props, "media.class").as_deref() != Some("Audio/Device") {
+                    continue;
+                }
+                let name = prop_str(props, "device.name").unwrap_or_default();
+                dump.devices.push(PwDevice {
+                    id: object.id,
+                    description: prop_str(props, "device.description")
+                        .unwrap_or_else(|| name.clone()),
+                    name,
+                    profiles: info.params.enum_profile,
+                    active_profile: info.params.profile.into_iter().next(),
+                    routes: info.params.enum_route,
+                    active_routes: info.params.route,
+                });
+            }
+            "PipeWire:Interface:Link" => {
+                if let (Some(output_node), Some(input_node)) =
+                    (info.output_node_id, info.input_node_id)
+                {
+                    dump.links.push(PwLink {
+                        output_node,
+                        input_node,
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(dump)
+}
+
+/// PipeWire stores linear channel volumes; wpctl shows and sets their cube
+/// root, so that is the scale used everywhere else.
+fn volume_from_props(props: &RawProps) -> Option<f32> {
+    if props.channel_volumes.is_empty() {
+        return None;
+    }
+    let linear = props.channel_volumes.iter().sum::<f32>() / props.channel_volumes.len() as f32;
+    Some(linear.cbrt())
+}
+
+fn prop_str(props: &Map<String, Value>, key: &str) -> Option<String> {
+    props
+        .get(key)
+        .and_then(Value::as_str)
+        .filter(|value| !value.is_empty())
+        .map(str::to_string)
+}
+
+/// Numeric props are integers in current pw-dump and strings in older
+/// releases.
+fn prop_u64(props: &Map<String, Value>, key: &str) -> Option<u64> {
+    match props.get(key)? {
+        Value::Number(number) => number.as_u64(),
+        Value::String(text) => text.parse().ok(),
+        _ => None,
+    }
+}
+
+/// Default-node metadata is `{"name": "..."}`, or the same JSON as a string
+/// in older releases.
+fn metadata_name(value: &Value) -> Option<String> {
+    match value {
+        Value::Object(map) => map.get("name").and_then(Value::as_str).map(str::to_string),
+        Value::String(text) => serde_json::from_str::<Value>(text)
+            .ok()
+            .and_then(|inner| inner.get("name")?.as_str().map(str::to_string)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_pw_dump, PwAvailability, PwDirection};
+
+    const LAPTOP_DUMP: &str = include_str!("../../tests/fixtures/pw-dump-laptop-nvidia-hdmi.json");
+    const DESKTOP_DUMP: &str = include_str!("../../tests/fixtures/pw-dump-desktop-de.json");
+
+    #[test]
+    fn parses_nodes_devices_and_links() {
+        let dump = parse_pw_dump(LAPTOP_DUMP).unwrap();
+        assert_eq!(
+            dump.default_sink.as_deref(),
+            Some("alsa_output.pci-0000_00_1f.3.analog-stereo")
+        );
+        assert_eq!(
+            dump.sinks().map(|sink| sink.id).collect::<Vec<_>>(),
+            vec![48, 61]
+        );
+        // Removed objects and video/MIDI nodes are not part of the model.
+        assert!(dump.node(90).is_none());
+        assert!(dump.node(33).is_none());
+
+        let intel = dump.device(42).unwrap();
+        assert_eq!(intel.description, "Built-in Audio");
+        assert_eq!(
+            intel
+                .active_profile
+                .as_ref()
+                .map(|profile| profile.name.as_str()),
+            Some("output:analog-stereo+input:analog-stereo")
+        );
+        let hdmi = intel
+            .profiles
+            .iter()
+            .find(|profile| profile.name == "output:hdmi-stereo")
+            .unwrap();
+        assert_eq!(hdmi.available, PwAvailability::No);
+        assert!(intel
+            .routes
+            .iter()
+            .any(|route| route.direction == PwDirection::Input));
+
+        let speakers = dump.node(48).unwrap();
+        assert!((speakers.volume.unwrap() - 0.7).abs() < 0.001);
+        assert!(!speakers.muted);
+        assert!(dump.node(61).unwrap().muted);
+        assert_eq!(
+            dump.active_route(speakers).map(|route| route.name.as_str()),
+            Some("analog-output-speaker")
+        );
+        let streams = dump.playback_streams().collect::<Vec<_>>();
+        assert_eq!(
+            streams
+                .iter()
+                .map(|stream| (stream.serial, dump.sink_of(stream).map(|sink| sink.id)))
+                .collect::<Vec<_>>(),
+            vec![(Some(512), Some(48)), (Some(530), Some(61))]
+        );
+        assert_eq!(streams[0].application.as_deref(), Some("Firefox"));
+        assert_eq!(streams[0].media_title.as_deref(), Some("AudioStream"));
+        assert_eq!(streams[1].media_title.as_deref(), Some("Big Buck Bunny"));
+    }
+
+    #[test]
+    fn parses_localized_dump_with_string_props() {
+        let dump = parse_pw_dump(DESKTOP_DUMP).unwrap();
+        let default = dump
+            .sinks()
+            .find(|sink| Some(&sink.name) == dump.default_sink.as_ref())
+            .unwrap();
+        assert_eq!(
+            default.description,
+            "Navi 21/23 HDMI/DP-Audio Digital Stereo (HDMI 2)"
+        );
+        assert_eq!(default.device_id, Some(57));
+        assert_eq!(
+            dump.active_route(default).map(|route| route.name.as_str()),
+            Some("hdmi-output-1")
+        );
+    }
+}
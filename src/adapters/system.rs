@@ -1,3 +1,5 @@
+use std::fs;
+use std::path::Path;
 use std::process::Command;
 
 #[derive(Debug, Default)]
@@ -31,4 +33,62 @@ impl SystemAdapter {
     pub fn any_path_exists(&self, paths: &[&str]) -> bool {
         paths.iter().any(|path| self.path_exists(path))
     }
+
+    /// PCI address of the GPU driving a DRM connector such as `HDMI-A-1`,
+    /// resolved through `/sys/class/drm/card*-<connector>`.
+    pub fn drm_connector_pci_address(&self, connector: &str) -> Option<String> {
+        fs::read_dir("/sys/class/drm")
+            .ok()?
+            .flatten()
+            .find(|entry| {
+                let name = entry.file_name();
+                let name = name.to_string_lossy();
+                name.starts_with("card")
+                    && name.split_once('-').map(|(_, conn)| conn) == Some(connector)
+            })
+            .and_then(|entry| fs::canonicalize(entry.path()).ok())
+            .and_then(|path| pci_address_in_path(&path))
+    }
+}
+
+/// The PCI device closest to the end of a sysfs path; behind a PCI bridge
+/// that is the GPU itself rather than the bridge.
+fn pci_address_in_path(path: &Path) -> Option<String> {
+    path.components()
+        .rev()
+        .filter_map(|component| component.as_os_str().to_str())
+        .find(|component| is_pci_address(component))
+        .map(str::to_string)
+}
+
+fn is_pci_address(value: &str) -> bool {
+    let bytes = value.as_bytes();
+    bytes.len() == 12
+        && bytes[4] == b':'
+        && bytes[7] == b':'
+        && bytes[10] == b'.'
+        && bytes
+            .iter()
+            .enumerate()
+            .filter(|(idx, _)| ![4, 7, 10].contains(idx))
+            .all(|(_, b)| b.is_ascii_hexdigit())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::pci_address_in_path;
+
+    #[test]
+    fn finds_gpu_behind_pci_bridge() {
+        let path = Path::new(
+            "/sys/devices/pci0000:00/0000:00:01.1/0000:01:00.0/drm/card1/card1-HDMI-A-1",
+        );
+        assert_eq!(pci_address_in_path(path), Some("0000:01:00.0".to_string()));
+        assert_eq!(
+            pci_address_in_path(Path::new("/sys/devices/platform/evdi.0/drm/card2/card2-DVI-I-1")),
+            None
+        );
+    }
 }
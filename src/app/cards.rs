@@ -0,0 +1,77 @@
+use crate::app::App;
+
+impl App {
+    pub fn select_next_audio_card(&mut self) {
+        if self.audio_cards.is_empty() {
+            self.log("no sound cards discovered");
+            return;
+        }
+        self.selected_audio_card = (self.selected_audio_card + 1) % self.audio_cards.len();
+        self.select_active_card_profile();
+    }
+
+    pub fn select_prev_audio_card(&mut self) {
+        if self.audio_cards.is_empty() {
+            self.log("no sound cards discovered");
+            return;
+        }
+        self.selected_audio_card =
+            (self.selected_audio_card + self.audio_cards.len() - 1) % self.audio_cards.len();
+        self.select_active_card_profile();
+    }
+
+    pub fn cycle_card_profile(&mut self, forward: bool) {
+        let Some(card) = self.audio_cards.get(self.selected_audio_card) else {
+            self.log("no sound cards discovered");
+            return;
+        };
+        let len = card.profiles.len();
+        if len == 0 {
+            return;
+        }
+        self.selected_card_profile = if forward {
+            (self.selected_card_profile + 1) % len
+        } else {
+            (self.selected_card_profile + len - 1) % len
+        };
+    }
+
+    /// Switches the selected card to the profile under the cursor, e.g. to
+    /// force `hdmi-stereo-extra1` when the automatic choice got it wrong.
+    pub fn apply_selected_card_profile(&mut self) {
+        let Some(card) = self.audio_cards.get(self.selected_audio_card).cloned() else {
+            self.log("no sound card selected");
+            return;
+        };
+        let Some(profile) = card.profiles.get(self.selected_card_profile) else {
+            self.log(format!("{} offers no profiles", card.name));
+            return;
+        };
+        if card.active_profile == Some(profile.index) {
+            self.log(format!("{} already uses {}", card.name, profile.name));
+            return;
+        }
+        match self.audio.set_card_profile(&card.id, profile.index) {
+            Ok(()) => self.log(format!(
+                "switched {} to {} ({})",
+                card.name, profile.name, profile.description
+            )),
+            Err(err) => self.log(format!("failed to switch {} profile: {err}", card.name)),
+        }
+        self.refresh_audio_devices();
+    }
+
+    /// Puts the profile cursor on the card's active profile.
+    pub fn select_active_card_profile(&mut self) {
+        self.selected_card_profile = self
+            .audio_cards
+            .get(self.selected_audio_card)
+            .and_then(|card| {
+                let active = card.active_profile?;
+                card.profiles
+                    .iter()
+                    .position(|profile| profile.index == active)
+            })
+            .unwrap_or(0);
+    }
+}
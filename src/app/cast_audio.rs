@@ -0,0 +1,175 @@
+use std::time::{Duration, Instant};
+
+use crate::adapters::audio::AudioSink;
+use crate::app::App;
+use crate::config::AudioRule;
+use crate::core::audio::choose_sink_for_gpu;
+use crate::core::orchestrator::{leave_tv_mode, switch_to_tv_mode};
+use crate::core::routing::{self, plan_moves, RouteTargets};
+
+/// How often pw-dump is polled for new streams while casting with rules.
+const STREAM_CHECK_INTERVAL: Duration = Duration::from_secs(2);
+
+impl App {
+    /// Routing rules only apply while casting; otherwise every stream
+    /// follows the default sink.
+    pub(super) fn audio_rules(&self) -> &[AudioRule] {
+        if self.cast_state.is_casting() {
+            &self.config.audio_rules
+        } else {
+            &[]
+        }
+    }
+
+    /// `node.name` of the cast output's HDMI sink, where `tv` rules send
+    /// audio: the one audio followed the display to, else the one on the
+    /// cast output's GPU.
+    fn cast_tv_node(&self, sinks: &[AudioSink]) -> Option<String> {
+        let target = self.cast_state.target()?;
+        if let Some(tv_audio) = self.tv_audio.as_ref().filter(|tv| tv.output == target) {
+            return Some(tv_audio.sink.node_name.clone());
+        }
+        self.sink_for_output(sinks, target)
+            .map(|sink| sink.node_name.clone())
+    }
+
+    /// The HDMI/DisplayPort sink that plays on `output`, matched through the
+    /// GPU driving the output's connector.
+    pub(super) fn sink_for_output<'a>(
+        &self,
+        sinks: &'a [AudioSink],
+        output: &str,
+    ) -> Option<&'a AudioSink> {
+        let gpu = self.system.drm_connector_pci_address(output);
+        choose_sink_for_gpu(sinks, gpu.as_deref())
+    }
+
+    /// Makes a sink the default and routes the playing streams by the rules
+    /// that currently apply.
+    pub(super) fn switch_default_sink(&self, sink_id: &str) -> anyhow::Result<usize> {
+        let tv_node = self.cast_tv_node(&self.audio_sinks);
+        routing::switch_default_sink(&self.audio, sink_id, self.audio_rules(), tv_node.as_deref())
+    }
+
+    pub fn toggle_audio_follows_display(&mut self) {
+        self.config.audio_follows_display = !self.config.audio_follows_display;
+        self.log(format!(
+            "audio follows display: {}",
+            if self.config.audio_follows_display {
+                "on"
+            } else {
+                "off"
+            }
+        ));
+    }
+
+    /// Moves audio to the HDMI sink of the current cast target. Re-applying
+    /// a mode on the same output leaves audio alone; moving the cast to
+    /// another output keeps the sink from before the first switch so
+    /// stopping still goes back to it.
+    pub(super) fn follow_display_audio(&mut self) {
+        let Some(target) = self.cast_state.target().map(str::to_string) else {
+            return;
+        };
+        if self
+            .tv_audio
+            .as_ref()
+            .is_some_and(|tv_audio| tv_audio.output == target)
+        {
+            return;
+        }
+
+        let rules = self.config.audio_rules.clone();
+        match switch_to_tv_mode(&self.audio, &self.system, &target, &rules) {
+            Ok((mut tv_audio, moved)) => {
+                self.log(format!(
+                    "audio follows {target}: {} (moved {moved} active stream(s))",
+                    tv_audio.sink.name
+                ));
+                if let Some(earlier) = self.tv_audio.take() {
+                    tv_audio.previous = earlier.previous;
+                }
+                self.tv_audio = Some(tv_audio);
+            }
+            Err(err) => self.log(format!("audio did not follow {target}: {err}")),
+        }
+        self.refresh_audio_devices();
+    }
+
+    /// Puts audio back where it was before casting started.
+    pub(super) fn restore_display_audio(&mut self) {
+        let Some(tv_audio) = self.tv_audio.take() else {
+            return;
+        };
+        match leave_tv_mode(&self.audio, &tv_audio) {
+            Ok(Some((sink, moved))) => self.log(format!(
+                "audio back on {} (moved {moved} active stream(s))",
+                sink.name
+            )),
+            Ok(None) => {}
+            Err(err) => self.log(format!("failed to move audio back: {err}")),
+        }
+        self.refresh_audio_devices();
+    }
+
+    /// Applies the routing rules to streams already playing when a cast
+    /// mode starts without audio following the display.
+    pub(super) fn apply_audio_rules(&mut self) {
+        self.route_streams(false);
+    }
+
+    /// Routes streams that appeared since the last look, at most every
+    /// couple of seconds and only while casting with rules configured.
+    pub(super) fn route_new_streams(&mut self) {
+        if !self.cast_state.is_casting()
+            || self.config.audio_rules.is_empty()
+            || Instant::now() < self.next_stream_check
+        {
+            return;
+        }
+        self.next_stream_check = Instant::now() + STREAM_CHECK_INTERVAL;
+        self.route_streams(true);
+    }
+
+    /// Uses a single pw-dump for both the routing and the Audio tab, since
+    /// this runs on the UI thread.
+    fn route_streams(&mut self, only_new: bool) {
+        let mut devices = match self.audio.list_devices() {
+            Ok(devices) => devices,
+            Err(err) => return self.log(format!("failed to list audio streams: {err}")),
+        };
+        let streams = devices
+            .streams
+            .iter()
+            .filter(|stream| !only_new || !self.routed_streams.contains(&stream.serial))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        let tv_node = self.cast_tv_node(&devices.sinks);
+        let moves = RouteTargets::from_sinks(&devices.sinks, tv_node.as_deref())
+            .map(|targets| {
+                plan_moves(&self.config.audio_rules, &streams, &targets)
+                    .into_iter()
+                    .map(|(stream, sink)| (stream.clone(), sink.clone()))
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        for (stream, sink) in moves {
+            match self.audio.move_stream(&stream, &sink) {
+                Ok(()) => {
+                    self.log(format!("routed {} to {}", stream.label(), sink.name));
+                    if let Some(moved) = devices
+                        .streams
+                        .iter_mut()
+                        .find(|known| known.serial == stream.serial)
+                    {
+                        moved.sink_id = Some(sink.id.clone());
+                    }
+                }
+                Err(err) => self.log(format!("failed to route {}: {err}", stream.label())),
+            }
+        }
+        self.routed_streams = devices.streams.iter().map(|stream| stream.serial).collect();
+        self.set_audio_devices(devices);
+    }
+}
@@ -0,0 +1,63 @@
+use crate::app::App;
+use crate::core::cast::DynamicCastTarget;
+
+impl App {
+    /// Points niri's dynamic screencast target at the cast target output.
+    pub fn set_dynamic_cast_to_target_output(&mut self) {
+        let Some(output) = self.cast_target_name() else {
+            self.log("no external output to share");
+            return;
+        };
+        let result = self.niri.set_dynamic_cast_monitor(&output);
+        self.finish_dynamic_cast_change(result, Some(DynamicCastTarget::Monitor(output)));
+    }
+
+    pub fn set_dynamic_cast_to_selected_window(&mut self) {
+        let Some(window) = self.windows.get(self.selected_window).cloned() else {
+            self.log("no window selected");
+            return;
+        };
+        let result = self.niri.set_dynamic_cast_window(window.id);
+        let target = DynamicCastTarget::Window {
+            id: window.id,
+            label: window.label(),
+        };
+        self.finish_dynamic_cast_change(result, Some(target));
+    }
+
+    pub fn clear_dynamic_cast(&mut self) {
+        let result = self.niri.clear_dynamic_cast_target();
+        self.finish_dynamic_cast_change(result, None);
+    }
+
+    /// A window target ends when the window closes.
+    pub(super) fn forget_closed_dynamic_cast_window(&mut self) {
+        let Some(DynamicCastTarget::Window { id, label }) = &self.dynamic_cast else {
+            return;
+        };
+        if !self.windows.iter().any(|w| w.id == *id) {
+            let line = format!("dynamic cast window closed: {label}");
+            self.dynamic_cast = None;
+            self.log(line);
+        }
+    }
+
+    fn finish_dynamic_cast_change(
+        &mut self,
+        result: anyhow::Result<()>,
+        target: Option<DynamicCastTarget>,
+    ) {
+        match result {
+            Ok(()) => {
+                match &target {
+                    Some(target) => self.log(format!("dynamic cast target: {}", target.describe())),
+                    None => self.log("dynamic cast target cleared"),
+                }
+                self.dynamic_cast = target;
+            }
+            Err(err) => self.log(format!(
+                "failed to change dynamic cast target (needs a niri release with dynamic cast support): {err}"
+            )),
+        }
+    }
+}
@@ -0,0 +1,78 @@
+use crate::app::App;
+use crate::core::niri_config::{KdlExport, NiriConfigFile};
+use crate::core::snapshot::{LayoutSnapshot, OutputSnapshot};
+
+const PROFILE_NAME: &str = "default-tv";
+
+impl App {
+    pub fn prepare_live_kdl_export(&mut self) {
+        let outputs = match self.niri.outputs_json() {
+            Ok(outputs) => LayoutSnapshot::capture(&outputs).outputs,
+            Err(err) => {
+                self.log(format!("failed to read outputs for export: {err}"));
+                return;
+            }
+        };
+        self.prepare_kdl_export("live layout", &outputs);
+    }
+
+    pub fn prepare_profile_kdl_export(&mut self) {
+        let layout = match self.profile_store.load_profile(PROFILE_NAME) {
+            Ok(Some(profile)) => profile.layout,
+            Ok(None) => {
+                self.log(format!("profile {PROFILE_NAME} not found"));
+                return;
+            }
+            Err(err) => {
+                self.log(format!("failed to load profile: {err}"));
+                return;
+            }
+        };
+        let Some(layout) = layout else {
+            self.log(format!(
+                "profile {PROFILE_NAME} has no saved layout; save it again with 's'"
+            ));
+            return;
+        };
+        self.prepare_kdl_export(&format!("profile {PROFILE_NAME}"), &layout.outputs);
+    }
+
+    fn prepare_kdl_export(&mut self, source: &str, outputs: &[OutputSnapshot]) {
+        let export =
+            NiriConfigFile::locate().and_then(|file| KdlExport::prepare(file, source, outputs));
+        match export {
+            Ok(export) if export.is_noop() => self.log(format!(
+                "{} already matches the {source}",
+                export.file.path().display()
+            )),
+            Ok(export) => {
+                self.log(format!(
+                    "review the config.kdl diff for the {source}: 'y' write, 'n' cancel"
+                ));
+                self.kdl_export = Some(export);
+            }
+            Err(err) => self.log(format!("failed to prepare config.kdl export: {err}")),
+        }
+    }
+
+    pub fn confirm_kdl_export(&mut self) {
+        let Some(export) = self.kdl_export.take() else {
+            return;
+        };
+        match export.file.write_with_backup(&export.content) {
+            Ok(Some(backup)) => self.log(format!(
+                "wrote {} (backup: {})",
+                export.file.path().display(),
+                backup.display()
+            )),
+            Ok(None) => self.log(format!("created {}", export.file.path().display())),
+            Err(err) => self.log(format!("failed to write config.kdl: {err}")),
+        }
+    }
+
+    pub fn cancel_kdl_export(&mut self) {
+        if self.kdl_export.take().is_some() {
+            self.log("config.kdl export cancelled");
+        }
+    }
+}
@@ -0,0 +1,210 @@
+use crate::app::App;
+use crate::core::layout::{snap_position, PlacedOutput, Rect};
+use crate::core::snapshot::LayoutSnapshot;
+use crate::core::transaction::LayoutTransaction;
+
+const MOVE_STEP: i32 = 100;
+const FINE_STEP: i32 = 10;
+const SNAP_THRESHOLD: i32 = 64;
+
+/// Draft positions for the enabled outputs while the layout editor is open.
+/// Nothing reaches niri until the draft is applied.
+#[derive(Debug, Clone)]
+pub struct LayoutEditor {
+    pub outputs: Vec<PlacedOutput>,
+    pub selected: usize,
+    opened_on: Vec<PlacedOutput>,
+}
+
+impl LayoutEditor {
+    fn new(outputs: Vec<PlacedOutput>, selected: usize) -> Self {
+        Self {
+            opened_on: outputs.clone(),
+            outputs,
+            selected,
+        }
+    }
+
+    /// Whether the live layout moved away from the one the draft started
+    /// from; applying the draft then would undo that change.
+    fn is_stale(&self, live: &[PlacedOutput]) -> bool {
+        self.opened_on.len() != live.len()
+            || self
+                .opened_on
+                .iter()
+                .zip(live)
+                .any(|(opened, live)| opened.name != live.name || opened.rect != live.rect)
+    }
+
+    pub fn selected_output(&self) -> Option<&PlacedOutput> {
+        self.outputs.get(self.selected)
+    }
+
+    /// The draft with the output being moved marked as the highlighted one.
+    pub fn placed(&self) -> Vec<PlacedOutput> {
+        self.outputs
+            .iter()
+            .enumerate()
+            .map(|(idx, output)| PlacedOutput {
+                is_target: idx == self.selected,
+                ..output.clone()
+            })
+            .collect()
+    }
+
+    fn move_selected(&mut self, dx: i32, dy: i32, snap: bool) {
+        let Some(moving) = self.outputs.get(self.selected).map(|o| o.rect) else {
+            return;
+        };
+        let moved = Rect {
+            x: moving.x + dx,
+            y: moving.y + dy,
+            ..moving
+        };
+        let (x, y) = if snap {
+            let others = self
+                .outputs
+                .iter()
+                .enumerate()
+                .filter(|(idx, _)| *idx != self.selected)
+                .map(|(_, o)| o.rect)
+                .collect::<Vec<_>>();
+            snap_position(moved, &others, SNAP_THRESHOLD)
+        } else {
+            (moved.x, moved.y)
+        };
+        let rect = &mut self.outputs[self.selected].rect;
+        rect.x = x;
+        rect.y = y;
+    }
+}
+
+impl App {
+    pub fn open_layout_editor(&mut self) {
+        let outputs = self.current_layout();
+        if outputs.is_empty() {
+            self.log("no enabled outputs to arrange");
+            return;
+        }
+        let selected = self
+            .selected_output()
+            .and_then(|output| outputs.iter().position(|o| o.name == output.name))
+            .unwrap_or(0);
+        self.layout_editor = Some(LayoutEditor::new(outputs, selected));
+        self.log("layout editor: arrows move (Shift for fine steps), j/k select, Enter apply, Esc cancel");
+    }
+
+    pub fn cancel_layout_editor(&mut self) {
+        if self.layout_editor.take().is_some() {
+            self.log("layout editor closed without changes");
+        }
+    }
+
+    /// Drops the draft once the outputs it was drawn on have changed.
+    pub(super) fn discard_stale_layout_draft(&mut self) {
+        let live = self.current_layout();
+        if self
+            .layout_editor
+            .as_ref()
+            .is_some_and(|editor| editor.is_stale(&live))
+        {
+            self.layout_editor = None;
+            self.log("layout changed while editing; layout editor draft discarded");
+        }
+    }
+
+    pub fn editor_select_next(&mut self) {
+        if let Some(editor) = &mut self.layout_editor {
+            editor.selected = (editor.selected + 1) % editor.outputs.len();
+        }
+    }
+
+    pub fn editor_select_prev(&mut self) {
+        if let Some(editor) = &mut self.layout_editor {
+            editor.selected = (editor.selected + editor.outputs.len() - 1) % editor.outputs.len();
+        }
+    }
+
+    /// Moves the selected draft output by one step in the given direction.
+    /// Fine steps skip snapping so an output can be nudged off an edge.
+    pub fn editor_move(&mut self, dx: i32, dy: i32, fine: bool) {
+        let step = if fine { FINE_STEP } else { MOVE_STEP };
+        if let Some(editor) = &mut self.layout_editor {
+            editor.move_selected(dx * step, dy * step, !fine);
+        }
+    }
+
+    pub fn apply_layout_editor(&mut self) {
+        let Some(editor) = self.layout_editor.take() else {
+            return;
+        };
+        match self.apply_positions(&editor.outputs) {
+            Ok(0) => self.log("layout editor: nothing moved"),
+            Ok(moved) => self.log(format!("layout editor: moved {moved} output(s)")),
+            Err(err) => self.log(format!("layout editor failed: {err}")),
+        }
+        self.refresh_outputs();
+    }
+
+    fn apply_positions(&mut self, draft: &[PlacedOutput]) -> anyhow::Result<usize> {
+        let live = self.niri.outputs_json()?;
+        let changed = draft
+            .iter()
+            .filter(|placed| {
+                live.iter()
+                    .find(|o| o.name == placed.name)
+                    .and_then(|o| o.logical.as_ref())
+                    .is_some_and(|l| (l.x, l.y) != (placed.rect.x, placed.rect.y))
+            })
+            .collect::<Vec<_>>();
+        if changed.is_empty() {
+            return Ok(0);
+        }
+
+        self.warn_about_layout(draft);
+        self.ensure_layout_snapshot()?;
+        let revert_to = LayoutSnapshot::capture(&live);
+        let mut tx = LayoutTransaction::new(self.niri.clone(), &live);
+        for placed in &changed {
+            if let Err(err) = tx.set_position(&placed.name, placed.rect.x, placed.rect.y) {
+                let report = tx.rollback();
+                self.log(format!("layout editor: {}", report.summary()));
+                return Err(err);
+            }
+        }
+        let description = format!("moved {} output(s)", changed.len());
+        let previous_state = self.cast_state.clone();
+        self.arm_revert_timer(&description, revert_to, previous_state);
+        Ok(changed.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LayoutEditor;
+    use crate::core::layout::{PlacedOutput, Rect};
+
+    fn placed(name: &str, x: i32) -> PlacedOutput {
+        PlacedOutput {
+            name: name.to_string(),
+            rect: Rect {
+                x,
+                y: 0,
+                width: 1920,
+                height: 1080,
+            },
+            is_target: false,
+        }
+    }
+
+    #[test]
+    fn draft_goes_stale_when_a_cast_moves_outputs() {
+        let mut editor = LayoutEditor::new(vec![placed("eDP-1", 0), placed("HDMI-A-1", 1920)], 1);
+        editor.move_selected(100, 0, false);
+        assert!(!editor.is_stale(&[placed("eDP-1", 0), placed("HDMI-A-1", 1920)]));
+
+        // Extend-left put the TV on the other side while the draft was open.
+        assert!(editor.is_stale(&[placed("eDP-1", 0), placed("HDMI-A-1", -1920)]));
+        assert!(editor.is_stale(&[placed("eDP-1", 0)]));
+    }
+}
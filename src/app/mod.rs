@@ -1,4 +1,6 @@
-use std::time::Duration;
+use std::collections::HashSet;
+use std::sync::mpsc::{Receiver, TryRecvError};
+use std::time::{Duration, Instant};
 
 use anyhow::Context;
 use crossterm::event::{self, Event};
@@ -10,49 +12,169 @@ use ratatui::backend::CrosstermBackend;
 use ratatui::Terminal;
 
 use crate::adapters::{
-    audio::{AudioAdapter, AudioSink},
-    niri::NiriAdapter,
+    audio::{AudioAdapter, AudioCard, AudioDevices, AudioSink, AudioSource, AudioStream},
+    niri::{NiriAdapter, NiriOutput, NiriWindow, NiriWorkspace},
+    niri_ipc::NiriEvent,
     portal::PortalAdapter,
     system::SystemAdapter,
     wl_mirror::WlMirrorAdapter,
 };
+use crate::config::AppConfig;
+use crate::core::audio::{switch_card_profile, ProfileTarget};
+use crate::core::cast::DynamicCastTarget;
+use crate::core::layout::{current_layout, extend_position, Alignment, LayoutCastMode, Rect};
+use crate::core::monitors::{
+    choose_cast_target, choose_primary_output, diff_output_names, OutputChanges,
+};
+use crate::core::niri_config::KdlExport;
+use crate::core::orchestrator::{move_workspaces_to, CastState, CastTransitionError, TvAudio};
+use crate::core::snapshot::{self, LayoutSnapshot, SnapshotStore};
+use crate::core::transaction::LayoutTransaction;
 use crate::diagnostics::{run_troubleshooting, TroubleshootReport};
-use crate::profiles::{ProfileStore, TvProfile};
+use crate::profiles::{choose_profile_for_outputs, ProfileStore, TvProfile};
 use crate::ui;
 
+mod cards;
+mod cast_audio;
+mod dynamic_cast;
+mod kdl_export;
+mod layout_editor;
+mod monitors;
+mod sources;
+mod streams;
+mod volume;
+mod windows;
+
+pub use layout_editor::LayoutEditor;
+pub use sources::AudioFocus;
+
+pub struct PendingRevert {
+    pub deadline: Instant,
+    pub description: String,
+    revert_to: LayoutSnapshot,
+    previous_state: CastState,
+}
+
+impl PendingRevert {
+    pub fn seconds_left(&self) -> u64 {
+        self.deadline
+            .saturating_duration_since(Instant::now())
+            .as_secs_f64()
+            .ceil() as u64
+    }
+}
+
 pub struct App {
+    pub config: AppConfig,
     pub selected_tab: usize,
     pub running: bool,
     pub log_lines: Vec<String>,
     pub last_outputs: Vec<String>,
+    pub outputs: Vec<NiriOutput>,
+    pub selected_output: usize,
+    pub selected_mode: usize,
     pub audio_sinks: Vec<AudioSink>,
     pub selected_audio_sink: usize,
+    pub audio_sources: Vec<AudioSource>,
+    pub selected_audio_source: usize,
+    pub audio_streams: Vec<AudioStream>,
+    pub selected_audio_stream: usize,
+    pub audio_cards: Vec<AudioCard>,
+    pub selected_audio_card: usize,
+    /// Profile cursor within the selected card.
+    pub selected_card_profile: usize,
+    pub audio_focus: AudioFocus,
     pub diagnostics: Option<TroubleshootReport>,
     pub profile_store: ProfileStore,
+    pub snapshot_store: SnapshotStore,
+    pub layout_snapshot: Option<LayoutSnapshot>,
+    pub pending_revert: Option<PendingRevert>,
     pub niri: NiriAdapter,
     pub audio: AudioAdapter,
     pub system: SystemAdapter,
     pub portal: PortalAdapter,
     pub wl_mirror: WlMirrorAdapter,
+    pub layout_alignment: Alignment,
+    pub cast_target: Option<String>,
+    pub preview_mode: Option<LayoutCastMode>,
+    pub layout_editor: Option<LayoutEditor>,
+    pub kdl_export: Option<KdlExport>,
+    pub windows: Vec<NiriWindow>,
+    pub workspaces: Vec<NiriWorkspace>,
+    pub selected_window: usize,
+    pub dynamic_cast: Option<DynamicCastTarget>,
+    pub cast_state: CastState,
+    pub tv_audio: Option<TvAudio>,
+    next_stream_check: Instant,
+    /// Serials of the streams the routing rules have already looked at, kept
+    /// apart from `audio_streams` since the Audio tab refreshes that list.
+    routed_streams: HashSet<u64>,
+    niri_events: Option<Receiver<NiriEvent>>,
 }
 
 impl App {
-    pub fn new() -> anyhow::Result<Self> {
-        Ok(Self {
+    pub fn new(config: AppConfig) -> anyhow::Result<Self> {
+        let snapshot_store = SnapshotStore::new()?;
+        let (layout_snapshot, snapshot_note) = match snapshot_store.load() {
+            Ok(Some(snapshot)) => {
+                let note = format!(
+                    "layout snapshot from {} is still pending; press 'u' to restore it",
+                    snapshot.taken_at
+                );
+                (Some(snapshot), Some(note))
+            }
+            Ok(None) => (None, None),
+            Err(err) => (None, Some(format!("ignoring unreadable layout snapshot: {err}"))),
+        };
+
+        let mut app = Self {
+            config,
             selected_tab: 0,
             running: true,
             log_lines: vec!["niri-cast started".to_string()],
             last_outputs: Vec::new(),
+            outputs: Vec::new(),
+            selected_output: 0,
+            selected_mode: 0,
             audio_sinks: Vec::new(),
             selected_audio_sink: 0,
+            audio_sources: Vec::new(),
+            selected_audio_source: 0,
+            audio_streams: Vec::new(),
+            selected_audio_stream: 0,
+            audio_cards: Vec::new(),
+            selected_audio_card: 0,
+            selected_card_profile: 0,
+            audio_focus: AudioFocus::default(),
             diagnostics: None,
             profile_store: ProfileStore::new()?,
-            niri: NiriAdapter::default(),
-            audio: AudioAdapter::default(),
-            system: SystemAdapter::default(),
-            portal: PortalAdapter::default(),
+            snapshot_store,
+            layout_snapshot,
+            pending_revert: None,
+            niri: NiriAdapter::from_env(),
+            audio: AudioAdapter,
+            system: SystemAdapter,
+            portal: PortalAdapter,
             wl_mirror: WlMirrorAdapter::default(),
-        })
+            layout_alignment: Alignment::default(),
+            cast_target: None,
+            preview_mode: None,
+            layout_editor: None,
+            kdl_export: None,
+            windows: Vec::new(),
+            workspaces: Vec::new(),
+            selected_window: 0,
+            dynamic_cast: None,
+            cast_state: CastState::Idle,
+            tv_audio: None,
+            next_stream_check: Instant::now(),
+            routed_streams: HashSet::new(),
+            niri_events: None,
+        };
+        if let Some(note) = snapshot_note {
+            app.log(note);
+        }
+        Ok(app)
     }
 
     pub fn shutdown(&mut self) {
@@ -82,25 +204,161 @@ impl App {
 
     pub fn refresh_discovery(&mut self) {
         self.refresh_outputs();
-        self.refresh_audio_sinks();
+        self.refresh_windows();
+        self.refresh_audio_devices();
         self.log(format!(
-            "discovered {} outputs, {} sinks",
+            "discovered {} outputs, {} sinks, {} sources",
             self.last_outputs.len(),
-            self.audio_sinks.len()
+            self.audio_sinks.len(),
+            self.audio_sources.len()
         ));
     }
 
-    fn refresh_outputs(&mut self) {
-        self.last_outputs = self.niri.list_outputs().unwrap_or_default();
+    pub(crate) fn refresh_outputs(&mut self) {
+        self.outputs = self.niri.outputs_json().unwrap_or_default();
+        self.last_outputs = self.outputs.iter().map(NiriOutput::summary_line).collect();
+        self.clamp_output_cursor();
+        self.discard_stale_layout_draft();
+    }
+
+    pub fn start_event_stream(&mut self) {
+        match self.niri.subscribe_events() {
+            Ok(events) => {
+                self.niri_events = Some(events);
+                self.log("watching niri event stream for output hotplug");
+            }
+            Err(err) => self.log(format!("niri event stream unavailable: {err}")),
+        }
+    }
+
+    pub fn tick(&mut self) {
+        self.drain_niri_events();
+        self.check_pending_revert();
+        self.route_new_streams();
+    }
+
+    fn drain_niri_events(&mut self) {
+        let mut outputs_dirty = false;
+        let mut windows_dirty = false;
+        while let Some(events) = &self.niri_events {
+            match events.try_recv() {
+                Ok(NiriEvent::StreamClosed(reason)) => {
+                    self.niri_events = None;
+                    self.log(format!("niri event stream closed: {reason}"));
+                }
+                Ok(NiriEvent::Undecodable { line, error }) => {
+                    self.log(format!("skipped niri event `{line}`: {error}"));
+                }
+                Ok(event) => {
+                    outputs_dirty |= event.may_change_outputs();
+                    windows_dirty |= matches!(
+                        event,
+                        NiriEvent::WindowsChanged | NiriEvent::WorkspacesChanged
+                    );
+                }
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => self.niri_events = None,
+            }
+        }
+
+        if outputs_dirty {
+            self.check_output_hotplug();
+        }
+        if windows_dirty {
+            self.refresh_windows();
+        }
+    }
+
+    fn check_output_hotplug(&mut self) {
+        let outputs = match self.niri.outputs_json() {
+            Ok(outputs) => outputs,
+            Err(err) => {
+                self.log(format!("output refresh after niri event failed: {err}"));
+                return;
+            }
+        };
+
+        let before = self.outputs.iter().map(|o| o.name.clone()).collect::<Vec<_>>();
+        let after = outputs.iter().map(|o| o.name.clone()).collect::<Vec<_>>();
+        self.outputs = outputs;
+        self.last_outputs = self.outputs.iter().map(NiriOutput::summary_line).collect();
+        self.clamp_output_cursor();
+        self.discard_stale_layout_draft();
+
+        let changes = diff_output_names(&before, &after);
+        if !changes.is_empty() {
+            self.on_outputs_changed(&changes);
+        }
+    }
+
+    fn on_outputs_changed(&mut self, changes: &OutputChanges) {
+        for name in &changes.connected {
+            self.log(format!("output connected: {name}"));
+        }
+        for name in &changes.disconnected {
+            self.log(format!("output disconnected: {name}"));
+        }
+        if let Some(target) = self.cast_state.target().map(str::to_string) {
+            if changes.disconnected.contains(&target) {
+                if matches!(self.cast_state, CastState::Mirrored { .. }) {
+                    if let Err(err) = self.wl_mirror.stop() {
+                        self.log(format!("failed to stop wl-mirror: {err}"));
+                    }
+                }
+                self.log(format!("cast target {target} went away; {} ended", self.cast_state));
+                if let Err(err) = self.set_cast_state(CastState::Idle) {
+                    self.log(format!("cast state: {err}"));
+                }
+                self.restore_display_audio();
+            }
+        }
+
+        if changes.connected.is_empty() {
+            return;
+        }
+
+        let profiles = match self.profile_store.list_profiles() {
+            Ok(profiles) => profiles,
+            Err(err) => {
+                self.log(format!("failed to read profiles: {err}"));
+                return;
+            }
+        };
+        if let Some(profile) = choose_profile_for_outputs(&profiles, &changes.connected) {
+            self.log(format!("profile {} matches newly connected output", profile.name));
+        }
+    }
+
+    fn refresh_audio_devices(&mut self) {
+        self.set_audio_devices(self.audio.list_devices().unwrap_or_default());
     }
 
-    fn refresh_audio_sinks(&mut self) {
-        self.audio_sinks = self.audio.list_sink_objects().unwrap_or_default();
+    fn set_audio_devices(&mut self, devices: AudioDevices) {
+        self.audio_sinks = devices.sinks;
+        self.audio_sources = devices.sources;
+        self.audio_streams = devices.streams;
+        self.audio_cards = devices.cards;
         if self.audio_sinks.is_empty() {
             self.selected_audio_sink = 0;
         } else if self.selected_audio_sink >= self.audio_sinks.len() {
             self.selected_audio_sink = self.audio_sinks.len() - 1;
         }
+        if self.selected_audio_source >= self.audio_sources.len() {
+            self.selected_audio_source = self.audio_sources.len().saturating_sub(1);
+        }
+        if self.selected_audio_stream >= self.audio_streams.len() {
+            self.selected_audio_stream = self.audio_streams.len().saturating_sub(1);
+        }
+        if self.selected_audio_card >= self.audio_cards.len() {
+            self.selected_audio_card = self.audio_cards.len().saturating_sub(1);
+        }
+        let profiles = self
+            .audio_cards
+            .get(self.selected_audio_card)
+            .map_or(0, |card| card.profiles.len());
+        if self.selected_card_profile >= profiles {
+            self.selected_card_profile = profiles.saturating_sub(1);
+        }
     }
 
     pub fn run_diagnostics(&mut self) {
@@ -115,12 +373,18 @@ impl App {
     }
 
     pub fn apply_hdmi_audio(&mut self) {
-        match self.audio.select_first_hdmi_sink() {
-            Ok(Some(sink)) => self.log(format!("set default audio sink: {sink}")),
-            Ok(None) => self.log("no HDMI sink found"),
-            Err(err) => self.log(format!("audio switch failed: {err}")),
+        let Some(target) = self.cast_target_name() else {
+            return self.log("no external output selected");
+        };
+        self.refresh_audio_devices();
+        match self.sink_for_output(&self.audio_sinks, &target).cloned() {
+            Some(sink) => match self.switch_default_sink(&sink.id) {
+                Ok(_) => self.log(format!("set default audio sink: {}", sink.display_line())),
+                Err(err) => self.log(format!("audio switch failed: {err}")),
+            },
+            None => self.log(format!("no HDMI sink found for {target}")),
         }
-        self.refresh_audio_sinks();
+        self.refresh_audio_devices();
     }
 
     pub fn select_next_audio_sink(&mut self) {
@@ -154,18 +418,18 @@ impl App {
         }
 
         let sink = self.audio_sinks[self.selected_audio_sink].clone();
-        match self.audio.set_default_and_move_streams_by_id(&sink.id) {
+        match self.switch_default_sink(&sink.id) {
             Ok(moved) => self.log(format!(
                 "set default audio sink: {}. {} (moved {} active stream(s))",
                 sink.id, sink.name, moved
             )),
             Err(err) => self.log(format!("failed to switch audio sink: {err}")),
         }
-        self.refresh_audio_sinks();
+        self.refresh_audio_devices();
     }
 
     pub fn switch_to_laptop_audio(&mut self) {
-        self.refresh_audio_sinks();
+        self.refresh_audio_devices();
         let target = self
             .audio_sinks
             .iter()
@@ -173,26 +437,32 @@ impl App {
             .cloned();
         match target {
             Some(sink) => {
-                match self.audio.set_default_and_move_streams_by_id(&sink.id) {
+                match self.switch_default_sink(&sink.id) {
                     Ok(moved) => self.log(format!(
                         "switched to laptop audio: {} (moved {} active stream(s))",
                         sink.name, moved
                     )),
                     Err(err) => self.log(format!("failed to switch to laptop audio: {err}")),
                 }
-                self.refresh_audio_sinks();
+                self.refresh_audio_devices();
             }
             None => {
-                match self.audio.try_switch_card_profile_to_laptop() {
-                    Ok(true) => {
-                        self.refresh_audio_sinks();
+                match switch_card_profile(
+                    &self.audio,
+                    &self.audio_cards,
+                    ProfileTarget::Laptop,
+                    None,
+                ) {
+                    Ok(Some(switched)) => {
+                        self.log(format!("switched card profile: {switched}"));
+                        self.refresh_audio_devices();
                         if let Some(sink) = self
                             .audio_sinks
                             .iter()
                             .find(|sink| !sink.is_hdmi)
                             .cloned()
                         {
-                            match self.audio.set_default_and_move_streams_by_id(&sink.id) {
+                            match self.switch_default_sink(&sink.id) {
                                 Ok(moved) => self.log(format!(
                                     "switched to laptop audio: {} (moved {} active stream(s))",
                                     sink.name, moved
@@ -201,13 +471,13 @@ impl App {
                                     self.log(format!("failed to set laptop audio sink: {err}"))
                                 }
                             }
-                            self.refresh_audio_sinks();
+                            self.refresh_audio_devices();
                         } else {
                             self.log("switched profile, but no laptop sink was exposed");
                         }
                     }
-                    Ok(false) => self.log(
-                        "no non-HDMI sink available; could not switch card profile to laptop audio",
+                    Ok(None) => self.log(
+                        "no non-HDMI sink available and no card offers an analog profile",
                     ),
                     Err(err) => self.log(format!("laptop profile switch failed: {err}")),
                 }
@@ -216,39 +486,52 @@ impl App {
     }
 
     pub fn switch_to_tv_audio(&mut self) {
-        self.refresh_audio_sinks();
-        let target = self.audio_sinks.iter().find(|sink| sink.is_hdmi).cloned();
+        let Some(output) = self.cast_target_name() else {
+            return self.log("no external output selected");
+        };
+        self.refresh_audio_devices();
+        let target = self.sink_for_output(&self.audio_sinks, &output).cloned();
         match target {
             Some(sink) => {
-                match self.audio.set_default_and_move_streams_by_id(&sink.id) {
+                match self.switch_default_sink(&sink.id) {
                     Ok(moved) => self.log(format!(
                         "switched to TV audio: {} (moved {} active stream(s))",
                         sink.name, moved
                     )),
                     Err(err) => self.log(format!("failed to switch to TV audio: {err}")),
                 }
-                self.refresh_audio_sinks();
+                self.refresh_audio_devices();
             }
             None => {
-                match self.audio.try_switch_card_profile_to_tv() {
-                    Ok(true) => {
-                        self.refresh_audio_sinks();
-                        if let Some(sink) = self.audio_sinks.iter().find(|sink| sink.is_hdmi).cloned()
+                let gpu = self.system.drm_connector_pci_address(&output);
+                match switch_card_profile(
+                    &self.audio,
+                    &self.audio_cards,
+                    ProfileTarget::Tv,
+                    gpu.as_deref(),
+                ) {
+                    Ok(Some(switched)) => {
+                        self.log(format!("switched card profile: {switched}"));
+                        self.refresh_audio_devices();
+                        if let Some(sink) =
+                            self.sink_for_output(&self.audio_sinks, &output).cloned()
                         {
-                            match self.audio.set_default_and_move_streams_by_id(&sink.id) {
+                            match self.switch_default_sink(&sink.id) {
                                 Ok(moved) => self.log(format!(
                                     "switched to TV audio: {} (moved {} active stream(s))",
                                     sink.name, moved
                                 )),
                                 Err(err) => self.log(format!("failed to set TV audio sink: {err}")),
                             }
-                            self.refresh_audio_sinks();
+                            self.refresh_audio_devices();
                         } else {
-                            self.log("switched profile, but no HDMI sink was exposed");
+                            self.log(format!(
+                                "switched profile, but no HDMI sink was exposed for {output}"
+                            ));
                         }
                     }
-                    Ok(false) => {
-                        self.log("no HDMI sink available; could not switch card profile to TV audio")
+                    Ok(None) => {
+                        self.log("no HDMI sink available and no card offers a usable HDMI profile")
                     }
                     Err(err) => self.log(format!("TV profile switch failed: {err}")),
                 }
@@ -256,21 +539,32 @@ impl App {
         }
     }
 
-    pub fn discover_hdmi_outputs(&mut self) {
-        match self.niri.list_hdmi_names() {
+    pub fn discover_external_outputs(&mut self) {
+        match self.niri.list_external_names() {
             Ok(outputs) if !outputs.is_empty() => {
-                self.log(format!("HDMI outputs: {}", outputs.join(", ")))
+                self.log(format!("external outputs: {}", outputs.join(", ")))
             }
-            Ok(_) => self.log("no HDMI outputs found"),
-            Err(err) => self.log(format!("HDMI discovery failed: {err}")),
+            Ok(_) => self.log("no external outputs found"),
+            Err(err) => self.log(format!("external output discovery failed: {err}")),
         }
     }
 
     pub fn save_profile(&mut self) {
+        let hdmi_output = self.cast_target_name();
+        let audio_sink = hdmi_output.as_deref().and_then(|output| {
+            let sinks = self.audio.list_sink_objects().ok()?;
+            self.sink_for_output(&sinks, output)
+                .map(|sink| sink.display_line())
+        });
         let profile = TvProfile {
             name: "default-tv".to_string(),
-            hdmi_output: self.niri.list_hdmi_names().ok().and_then(|mut v| v.pop()),
-            audio_sink: self.audio.find_first_hdmi_sink().ok().flatten(),
+            hdmi_output,
+            audio_sink,
+            layout: self
+                .niri
+                .outputs_json()
+                .ok()
+                .map(|outputs| LayoutSnapshot::capture(&outputs)),
         };
 
         match self.profile_store.save_profile(profile) {
@@ -304,30 +598,50 @@ impl App {
     }
 
     pub fn cast_extend_right(&mut self) {
-        match self.apply_layout_cast(LayoutCastMode::ExtendRight) {
-            Ok(msg) => self.log(msg),
-            Err(err) => self.log(format!("extend-right failed: {err}")),
-        }
+        self.run_cast_mode(LayoutCastMode::ExtendRight);
     }
 
     pub fn cast_extend_left(&mut self) {
-        match self.apply_layout_cast(LayoutCastMode::ExtendLeft) {
-            Ok(msg) => self.log(msg),
-            Err(err) => self.log(format!("extend-left failed: {err}")),
-        }
+        self.run_cast_mode(LayoutCastMode::ExtendLeft);
+    }
+
+    pub fn cast_extend_above(&mut self) {
+        self.run_cast_mode(LayoutCastMode::ExtendAbove);
+    }
+
+    pub fn cast_extend_below(&mut self) {
+        self.run_cast_mode(LayoutCastMode::ExtendBelow);
+    }
+
+    pub fn cycle_layout_alignment(&mut self) {
+        self.layout_alignment = self.layout_alignment.next();
+        self.log(format!(
+            "extend alignment: {}",
+            self.layout_alignment.label()
+        ));
     }
 
     pub fn cast_mirror(&mut self) {
-        match self.apply_layout_cast(LayoutCastMode::Mirror) {
-            Ok(msg) => self.log(msg),
-            Err(err) => self.log(format!("mirror failed: {err}")),
-        }
+        self.run_cast_mode(LayoutCastMode::Mirror);
     }
 
     pub fn cast_hdmi_only(&mut self) {
-        match self.apply_layout_cast(LayoutCastMode::HdmiOnly) {
-            Ok(msg) => self.log(msg),
-            Err(err) => self.log(format!("hdmi-only failed: {err}")),
+        self.run_cast_mode(LayoutCastMode::HdmiOnly);
+    }
+
+    /// Applies a cast mode and, when audio follows the display, moves
+    /// audio onto the cast output in the same step.
+    fn run_cast_mode(&mut self, mode: LayoutCastMode) {
+        match self.apply_layout_cast(mode) {
+            Ok(msg) => {
+                self.log(msg);
+                if self.config.audio_follows_display {
+                    self.follow_display_audio();
+                } else if !self.config.audio_rules.is_empty() {
+                    self.apply_audio_rules();
+                }
+            }
+            Err(err) => self.log(format!("{} failed: {err}", mode.label())),
         }
     }
 
@@ -339,99 +653,332 @@ impl App {
     }
 
     fn apply_layout_cast(&mut self, mode: LayoutCastMode) -> anyhow::Result<String> {
+        self.ensure_layout_snapshot()?;
+        let revert_to = self.capture_layout()?;
+        let previous_state = self.cast_state.clone();
+
+        let msg = self.apply_layout_cast_inner(mode)?;
+        self.arm_revert_timer(&msg, revert_to, previous_state);
+        Ok(msg)
+    }
+
+    fn arm_revert_timer(
+        &mut self,
+        description: &str,
+        revert_to: LayoutSnapshot,
+        previous_state: CastState,
+    ) {
+        if self.config.revert_timeout_secs == 0 {
+            return;
+        }
+        // A second change inside the window still reverts to the layout
+        // from before the first unconfirmed change.
+        let (revert_to, previous_state) = match self.pending_revert.take() {
+            Some(pending) => (pending.revert_to, pending.previous_state),
+            None => (revert_to, previous_state),
+        };
+        self.pending_revert = Some(PendingRevert {
+            deadline: Instant::now() + Duration::from_secs(self.config.revert_timeout_secs),
+            description: description.to_string(),
+            revert_to,
+            previous_state,
+        });
+    }
+
+    pub fn confirm_layout(&mut self) {
+        if self.pending_revert.take().is_some() {
+            self.log("kept new layout");
+        }
+    }
+
+    pub fn revert_layout_now(&mut self) {
+        if let Some(pending) = self.pending_revert.take() {
+            self.revert_layout(pending, "reverted layout");
+        }
+    }
+
+    fn check_pending_revert(&mut self) {
+        let expired = self
+            .pending_revert
+            .as_ref()
+            .is_some_and(|pending| Instant::now() >= pending.deadline);
+        if expired {
+            if let Some(pending) = self.pending_revert.take() {
+                self.revert_layout(pending, "layout not confirmed in time; reverted");
+            }
+        }
+    }
+
+    /// Puts back the layout from before the unconfirmed change. The layout
+    /// snapshot from before the first cast is kept; only an explicit stop
+    /// restores and clears that one.
+    fn revert_layout(&mut self, pending: PendingRevert, reason: &str) {
+        let PendingRevert {
+            revert_to,
+            previous_state,
+            ..
+        } = pending;
+        let result = self.wl_mirror.stop().and_then(|()| {
+            let missing = snapshot::restore(&self.niri, &revert_to)?;
+            if matches!(previous_state, CastState::Mirrored { .. }) {
+                self.apply_layout_cast_inner(LayoutCastMode::Mirror)?;
+            }
+            Ok(missing)
+        });
+
+        match result {
+            Ok(missing) => {
+                let msg = if previous_state.is_casting() {
+                    "previous cast layout is back"
+                } else {
+                    "previous layout is back"
+                };
+                if !previous_state.is_casting() && self.cast_state.is_casting() {
+                    self.restore_display_audio();
+                }
+                // Editor changes leave the state as it was.
+                if previous_state != self.cast_state {
+                    if let Err(err) = self.set_cast_state(previous_state) {
+                        self.log(format!("cast state: {err}"));
+                    }
+                }
+                if missing.is_empty() {
+                    self.log(format!("{reason}: {msg}"));
+                } else {
+                    self.log(format!(
+                        "{reason}: {msg} (skipped disconnected: {})",
+                        missing.join(", ")
+                    ));
+                }
+            }
+            Err(err) => self.log(format!("{reason}, but restoring failed: {err}")),
+        }
+        if self.cast_state.is_casting() && self.config.audio_follows_display {
+            self.follow_display_audio();
+        }
+        self.refresh_outputs();
+    }
+
+    /// Runs a cast mode as one transaction: when a step fails, everything
+    /// already changed is undone in reverse order and logged.
+    fn apply_layout_cast_inner(&mut self, mode: LayoutCastMode) -> anyhow::Result<String> {
         if !matches!(mode, LayoutCastMode::Mirror) {
             self.wl_mirror.stop()?;
         }
 
-        let mut outputs = self.niri.outputs_json()?;
-        let hdmi_name = outputs
-            .iter()
-            .find(|o| o.name.to_ascii_uppercase().contains("HDMI"))
+        let outputs = self.niri.outputs_json()?;
+        let target = choose_cast_target(&outputs, self.cast_target.as_deref())
+            .ok_or_else(|| anyhow::anyhow!("no external output available"))?;
+        let primary = choose_primary_output(&outputs, &target.name).map(|o| o.name.as_str());
+        // Fails before any output changes when the mode cannot apply, e.g.
+        // mirroring without a second output.
+        CastState::for_mode(mode, &target.name, primary)?;
+
+        let mut tx = LayoutTransaction::new(self.niri.clone(), &outputs);
+        let result = self
+            .run_layout_cast(mode, &outputs, &mut tx)
+            .and_then(|(msg, state)| {
+                self.set_cast_state(state)?;
+                Ok(msg)
+            });
+        match result {
+            Ok(msg) => Ok(msg),
+            Err(err) => {
+                let report = tx.rollback();
+                self.log(format!("{} failed: {}", mode.label(), report.summary()));
+                Err(err)
+            }
+        }
+    }
+
+    fn run_layout_cast(
+        &mut self,
+        mode: LayoutCastMode,
+        outputs: &[NiriOutput],
+        tx: &mut LayoutTransaction,
+    ) -> anyhow::Result<(String, CastState)> {
+        let target_name = choose_cast_target(outputs, self.cast_target.as_deref())
             .map(|o| o.name.clone())
-            .ok_or_else(|| anyhow::anyhow!("no HDMI output available"))?;
+            .ok_or_else(|| anyhow::anyhow!("no external output available"))?;
 
-        self.niri.output_on(&hdmi_name)?;
+        tx.output_on(&target_name)?;
 
         if matches!(mode, LayoutCastMode::Mirror) {
-            if let Some(non_hdmi_name) = self
-                .niri
-                .connected_output_names()?
-                .into_iter()
-                .find(|name| !name.to_ascii_uppercase().contains("HDMI"))
-            {
-                self.niri.output_on(&non_hdmi_name)?;
+            if let Some(source) = choose_primary_output(outputs, &target_name) {
+                tx.output_on(&source.name)?;
             }
-            outputs = self.niri.outputs_json()?;
         }
 
-        let hdmi_output = outputs
-            .iter()
-            .find(|o| o.name == hdmi_name)
-            .ok_or_else(|| anyhow::anyhow!("could not read HDMI output logical info"))?;
+        // Outputs that were just turned on only report logical geometry now.
+        let outputs = self.niri.outputs_json()?;
 
-        let non_hdmi_primary = outputs
+        let target = outputs
             .iter()
-            .find(|o| !o.name.to_ascii_uppercase().contains("HDMI"));
+            .find(|o| o.name == target_name)
+            .ok_or_else(|| anyhow::anyhow!("could not read {target_name} logical info"))?;
+        if target.logical.is_none() {
+            return Err(anyhow::anyhow!("{target_name} has no logical geometry"));
+        }
+        let target_size = target
+            .logical_size()
+            .ok_or_else(|| anyhow::anyhow!("could not determine size of {target_name}"))?;
+
+        let primary = choose_primary_output(&outputs, &target_name).filter(|o| o.logical.is_some());
 
         match mode {
-            LayoutCastMode::ExtendRight => {
-                let primary = non_hdmi_primary.unwrap_or(hdmi_output);
-                self.niri.set_position(
-                    &hdmi_output.name,
-                    primary.logical.x + primary.logical.width,
-                    primary.logical.y,
-                )?;
-                if primary.name != hdmi_output.name {
-                    self.niri.output_on(&primary.name)?;
-                }
-                Ok(format!(
-                    "cast mode set: extend-right ({} right of {})",
-                    hdmi_output.name, primary.name
-                ))
-            }
-            LayoutCastMode::ExtendLeft => {
-                let primary = non_hdmi_primary.unwrap_or(hdmi_output);
-                self.niri.set_position(
-                    &hdmi_output.name,
-                    primary.logical.x - hdmi_output.logical.width,
-                    primary.logical.y,
-                )?;
-                if primary.name != hdmi_output.name {
-                    self.niri.output_on(&primary.name)?;
+            LayoutCastMode::ExtendRight
+            | LayoutCastMode::ExtendLeft
+            | LayoutCastMode::ExtendAbove
+            | LayoutCastMode::ExtendBelow => {
+                let side = mode
+                    .extend_side()
+                    .ok_or_else(|| anyhow::anyhow!("{} is not an extend mode", mode.label()))?;
+                let (primary, primary_logical) = primary
+                    .and_then(|o| o.logical.as_ref().map(|logical| (o, logical)))
+                    .ok_or_else(|| anyhow::anyhow!("no primary output to extend from"))?;
+                let primary_rect = Rect::from(primary_logical);
+                let (x, y) = extend_position(primary_rect, target_size, side, self.layout_alignment);
+                let mut placed = current_layout(&outputs, Some(&target.name));
+                for output in placed.iter_mut().filter(|p| p.is_target) {
+                    output.rect = Rect {
+                        x,
+                        y,
+                        width: target_size.0,
+                        height: target_size.1,
+                    };
                 }
-                Ok(format!(
-                    "cast mode set: extend-left ({} left of {})",
-                    hdmi_output.name, primary.name
-                ))
+                self.warn_about_layout(&placed);
+                tx.set_position(&target.name, x, y)?;
+                tx.output_on(&primary.name)?;
+                let msg = format!(
+                    "cast mode set: {} ({} {} {}, {} aligned)",
+                    mode.label(),
+                    target.name,
+                    side.relation(),
+                    primary.name,
+                    self.layout_alignment.label()
+                );
+                let state = CastState::Extended {
+                    side,
+                    target: target.name.clone(),
+                };
+                Ok((msg, state))
             }
             LayoutCastMode::Mirror => {
-                let primary = non_hdmi_primary
-                    .ok_or_else(|| anyhow::anyhow!("no non-HDMI source output available"))?;
-                self.niri.output_on(&primary.name)?;
-                self.wl_mirror.start(&primary.name, &hdmi_output.name)?;
-                Ok(format!(
+                let primary =
+                    primary.ok_or_else(|| anyhow::anyhow!("no source output available to mirror"))?;
+                tx.output_on(&primary.name)?;
+                let state = CastState::for_mode(mode, &target.name, Some(&primary.name))?;
+                self.wl_mirror.start(&primary.name, &target.name)?;
+                let msg = format!(
                     "cast mode set: wl-mirror (source={}, target={})",
-                    primary.name, hdmi_output.name
-                ))
+                    primary.name, target.name
+                );
+                Ok((msg, state))
             }
             LayoutCastMode::HdmiOnly => {
+                // Move workspaces first so niri does not scatter them when
+                // their monitors turn off.
+                let workspaces = self.niri.workspaces()?;
+                let moved = move_workspaces_to(tx, &workspaces, &target.name)?;
                 for output in &outputs {
-                    if output.name == hdmi_output.name {
-                        self.niri.output_on(&output.name)?;
+                    if output.name == target.name {
+                        tx.output_on(&output.name)?;
                     } else {
-                        self.niri.output_off(&output.name)?;
+                        tx.output_off(&output.name)?;
                     }
                 }
-                Ok(format!("cast mode set: hdmi-only ({})", hdmi_output.name))
+                let msg = if moved.is_empty() {
+                    format!("cast mode set: hdmi-only ({})", target.name)
+                } else {
+                    format!(
+                        "cast mode set: hdmi-only ({}, moved workspaces {})",
+                        target.name,
+                        moved.join(", ")
+                    )
+                };
+                let state = CastState::HdmiOnly {
+                    target: target.name.clone(),
+                };
+                Ok((msg, state))
             }
         }
     }
 
+    /// Outputs plus the workspace-to-monitor mapping, so restoring also
+    /// undoes workspace moves made by hdmi-only.
+    fn capture_layout(&self) -> anyhow::Result<LayoutSnapshot> {
+        let snapshot = LayoutSnapshot::capture(&self.niri.outputs_json()?);
+        Ok(snapshot.with_workspaces(&self.niri.workspaces()?))
+    }
+
+    /// Captures the layout the user had before the first cast mode. Later
+    /// mode switches keep the original snapshot so `u` always goes back to
+    /// the hand-tuned layout rather than an intermediate cast layout.
+    fn ensure_layout_snapshot(&mut self) -> anyhow::Result<()> {
+        if self.layout_snapshot.is_some() {
+            return Ok(());
+        }
+        let snapshot = self.capture_layout()?;
+        self.snapshot_store.save(&snapshot)?;
+        self.log(format!(
+            "saved layout snapshot of {} output(s)",
+            snapshot.outputs.len()
+        ));
+        self.layout_snapshot = Some(snapshot);
+        Ok(())
+    }
+
+    /// Every cast state change goes through `CastState::transition`.
+    fn set_cast_state(&mut self, next: CastState) -> Result<(), CastTransitionError> {
+        self.cast_state = self.cast_state.transition(next)?;
+        Ok(())
+    }
+
+    /// Stops casting. The cast state decides what has to be torn down
+    /// (only mirroring runs a wl-mirror child) before the snapshot from
+    /// before the first cast mode is restored.
     fn restore_all_outputs(&mut self) -> anyhow::Result<String> {
+        let stopping = self.cast_state.clone();
+        // A snapshot left behind by a crash is restored without a cast
+        // running; otherwise there has to be something to stop.
+        let next = match stopping.transition(CastState::Idle) {
+            Ok(next) => next,
+            Err(CastTransitionError::NotCasting) if self.layout_snapshot.is_some() => {
+                CastState::Idle
+            }
+            Err(err) => return Err(err.into()),
+        };
+        self.pending_revert = None;
+        if matches!(stopping, CastState::Mirrored { .. }) {
+            self.wl_mirror.stop()?;
+        }
+        let stopped = if stopping.is_casting() {
+            format!("stopped {stopping}; ")
+        } else {
+            String::new()
+        };
+        self.restore_display_audio();
+
+        if let Some(layout) = self.layout_snapshot.clone() {
+            let missing = snapshot::restore(&self.niri, &layout)?;
+            self.layout_snapshot = None;
+            self.cast_state = next;
+            self.snapshot_store.clear()?;
+            self.refresh_outputs();
+
+            let mut msg = format!("{stopped}restored layout snapshot from {}", layout.taken_at);
+            if !missing.is_empty() {
+                msg.push_str(&format!(" (skipped disconnected: {})", missing.join(", ")));
+            }
+            return Ok(msg);
+        }
+
         let names = self.niri.connected_output_names()?;
         if names.is_empty() {
             return Err(anyhow::anyhow!("no connected outputs found"));
         }
+        self.cast_state = next;
 
         for name in &names {
             self.niri.output_on(name)?;
@@ -440,27 +987,23 @@ impl App {
             self.niri.set_position_auto(name)?;
         }
 
-        Ok(format!("restored outputs: {}", names.join(", ")))
+        Ok(format!(
+            "{stopped}no layout snapshot; restored outputs: {} (on + auto position)",
+            names.join(", ")
+        ))
     }
 }
 
-#[derive(Debug, Clone, Copy)]
-enum LayoutCastMode {
-    ExtendRight,
-    ExtendLeft,
-    Mirror,
-    HdmiOnly,
-}
-
-pub fn run() -> anyhow::Result<()> {
+pub fn run(config: AppConfig) -> anyhow::Result<()> {
     enable_raw_mode().context("failed to enable raw mode")?;
     let mut stdout = std::io::stdout();
     execute!(stdout, EnterAlternateScreen).context("failed to enter alternate screen")?;
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend).context("failed to initialize terminal")?;
 
-    let mut app = App::new()?;
+    let mut app = App::new(config)?;
     app.refresh_discovery();
+    app.start_event_stream();
 
     let result = run_event_loop(&mut terminal, &mut app);
     app.shutdown();
@@ -477,6 +1020,7 @@ fn run_event_loop(
     app: &mut App,
 ) -> anyhow::Result<()> {
     while app.running {
+        app.tick();
         terminal.draw(|frame| ui::render(frame, app))?;
 
         if event::poll(Duration::from_millis(50))? {
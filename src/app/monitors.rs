@@ -0,0 +1,290 @@
+use crate::adapters::niri::{NiriOutput, NiriTransform};
+use crate::app::App;
+use crate::core::layout::{current_layout, project_layout, LayoutCastMode, PlacedOutput};
+use crate::core::monitors::choose_cast_target;
+use crate::core::validation::validate_layout;
+
+const SCALE_STEP: f64 = 0.25;
+const MIN_SCALE: f64 = 0.5;
+const MAX_SCALE: f64 = 4.0;
+
+impl App {
+    pub fn selected_output(&self) -> Option<&NiriOutput> {
+        self.outputs.get(self.selected_output)
+    }
+
+    /// The output every cast mode targets: the user's pick when it is still
+    /// connected, otherwise the first external output.
+    pub fn cast_target_name(&self) -> Option<String> {
+        choose_cast_target(&self.outputs, self.cast_target.as_deref()).map(|o| o.name.clone())
+    }
+
+    pub fn external_outputs(&self) -> Vec<&NiriOutput> {
+        self.outputs.iter().filter(|o| !o.is_internal()).collect()
+    }
+
+    pub fn select_next_cast_target(&mut self) {
+        self.step_cast_target(1);
+    }
+
+    pub fn select_prev_cast_target(&mut self) {
+        self.step_cast_target(-1);
+    }
+
+    fn step_cast_target(&mut self, delta: isize) {
+        let names = self
+            .external_outputs()
+            .iter()
+            .map(|o| o.name.clone())
+            .collect::<Vec<_>>();
+        if names.is_empty() {
+            self.log("no external outputs to cast to");
+            return;
+        }
+        let current = self
+            .cast_target_name()
+            .and_then(|name| names.iter().position(|n| *n == name))
+            .unwrap_or(0);
+        let next = (current as isize + delta).rem_euclid(names.len() as isize) as usize;
+        self.cast_target = Some(names[next].clone());
+        self.log(format!("cast target: {}", names[next]));
+    }
+
+    /// Steps the Monitors preview through no projection and each cast mode.
+    pub fn cycle_preview_mode(&mut self) {
+        let modes = LayoutCastMode::ALL;
+        self.preview_mode = match self.preview_mode {
+            None => Some(modes[0]),
+            Some(mode) => modes
+                .iter()
+                .position(|m| *m == mode)
+                .and_then(|i| modes.get(i + 1).copied()),
+        };
+        match self.preview_mode {
+            Some(mode) => self.log(format!("preview: {}", mode.label())),
+            None => self.log("preview: current layout only"),
+        }
+    }
+
+    pub fn current_layout(&self) -> Vec<PlacedOutput> {
+        current_layout(&self.outputs, self.cast_target_name().as_deref())
+    }
+
+    pub fn projected_layout(&self) -> Option<Vec<PlacedOutput>> {
+        let mode = self.preview_mode?;
+        let target = self.cast_target_name()?;
+        Some(project_layout(
+            &self.outputs,
+            mode,
+            &target,
+            self.layout_alignment,
+        ))
+    }
+
+    /// Logs validator warnings for a layout that is about to be applied.
+    /// Warnings never block the change; the revert prompt covers mistakes.
+    pub(super) fn warn_about_layout(&mut self, placed: &[PlacedOutput]) {
+        for warning in validate_layout(placed) {
+            self.log(format!("layout warning: {warning}"));
+        }
+    }
+
+    pub fn select_next_output(&mut self) {
+        if self.outputs.is_empty() {
+            self.log("no outputs discovered");
+            return;
+        }
+        self.selected_output = (self.selected_output + 1) % self.outputs.len();
+        self.reset_mode_cursor();
+        self.log_selected_output();
+    }
+
+    pub fn select_prev_output(&mut self) {
+        if self.outputs.is_empty() {
+            self.log("no outputs discovered");
+            return;
+        }
+        if self.selected_output == 0 {
+            self.selected_output = self.outputs.len() - 1;
+        } else {
+            self.selected_output -= 1;
+        }
+        self.reset_mode_cursor();
+        self.log_selected_output();
+    }
+
+    pub fn select_next_mode(&mut self) {
+        let Some(count) = self.selected_output().map(|o| o.modes.len()) else {
+            self.log("no output selected");
+            return;
+        };
+        if count == 0 {
+            self.log("selected output reports no modes");
+            return;
+        }
+        self.selected_mode = (self.selected_mode + 1) % count;
+        self.log_selected_mode();
+    }
+
+    pub fn select_prev_mode(&mut self) {
+        let Some(count) = self.selected_output().map(|o| o.modes.len()) else {
+            self.log("no output selected");
+            return;
+        };
+        if count == 0 {
+            self.log("selected output reports no modes");
+            return;
+        }
+        if self.selected_mode == 0 {
+            self.selected_mode = count - 1;
+        } else {
+            self.selected_mode -= 1;
+        }
+        self.log_selected_mode();
+    }
+
+    pub fn apply_selected_mode(&mut self) {
+        let Some(output) = self.selected_output().cloned() else {
+            self.log("no output selected");
+            return;
+        };
+        let Some(mode) = output.modes.get(self.selected_mode).cloned() else {
+            self.log(format!("{} reports no modes", output.name));
+            return;
+        };
+
+        match self.niri.set_mode(&output.name, &mode) {
+            Ok(()) => self.log(format!("set mode for {}: {}", output.name, mode.label())),
+            Err(err) => self.log(format!("failed to set mode for {}: {err}", output.name)),
+        }
+        self.refresh_outputs();
+    }
+
+    pub fn increase_selected_scale(&mut self) {
+        self.step_selected_scale(SCALE_STEP);
+    }
+
+    pub fn decrease_selected_scale(&mut self) {
+        self.step_selected_scale(-SCALE_STEP);
+    }
+
+    pub fn rotate_selected_output(&mut self) {
+        self.update_selected_transform(NiriTransform::rotated);
+    }
+
+    pub fn flip_selected_output(&mut self) {
+        self.update_selected_transform(NiriTransform::flipped);
+    }
+
+    fn step_selected_scale(&mut self, delta: f64) {
+        let Some(output) = self.selected_output().cloned() else {
+            self.log("no output selected");
+            return;
+        };
+        let Some(logical) = &output.logical else {
+            self.log(format!("{} is disabled; turn it on before scaling", output.name));
+            return;
+        };
+
+        let scale = ((logical.scale + delta) / SCALE_STEP).round() * SCALE_STEP;
+        let scale = scale.clamp(MIN_SCALE, MAX_SCALE);
+        if (scale - logical.scale).abs() < f64::EPSILON {
+            self.log(format!("{} already at scale {scale}", output.name));
+            return;
+        }
+
+        match self.niri.set_scale(&output.name, scale) {
+            Ok(()) => {
+                self.log(format!("set scale for {}: {scale}", output.name));
+                self.after_output_geometry_change();
+            }
+            Err(err) => self.log(format!("failed to set scale for {}: {err}", output.name)),
+        }
+    }
+
+    fn update_selected_transform(&mut self, next: fn(NiriTransform) -> NiriTransform) {
+        let Some(output) = self.selected_output().cloned() else {
+            self.log("no output selected");
+            return;
+        };
+        let Some(logical) = &output.logical else {
+            self.log(format!("{} is disabled; turn it on before rotating", output.name));
+            return;
+        };
+
+        let transform = next(logical.transform);
+        match self.niri.set_transform(&output.name, transform) {
+            Ok(()) => {
+                self.log(format!(
+                    "set transform for {}: {}",
+                    output.name,
+                    transform.as_str()
+                ));
+                self.after_output_geometry_change();
+            }
+            Err(err) => self.log(format!(
+                "failed to set transform for {}: {err}",
+                output.name
+            )),
+        }
+    }
+
+    /// Scale and transform change an output's logical size, so an active
+    /// extend layout is recomputed against the new sizes.
+    fn after_output_geometry_change(&mut self) {
+        self.refresh_outputs();
+        let Some(mode) = self.cast_state.layout_mode() else {
+            return;
+        };
+        if mode.extend_side().is_some() {
+            match self.apply_layout_cast(mode) {
+                Ok(msg) => self.log(format!("re-applied layout: {msg}")),
+                Err(err) => self.log(format!("failed to re-apply layout: {err}")),
+            }
+            self.refresh_outputs();
+        }
+    }
+
+    pub(super) fn clamp_output_cursor(&mut self) {
+        if self.outputs.is_empty() {
+            self.selected_output = 0;
+            self.selected_mode = 0;
+            return;
+        }
+        if self.selected_output >= self.outputs.len() {
+            self.selected_output = self.outputs.len() - 1;
+        }
+        let mode_count = self.outputs[self.selected_output].modes.len();
+        if self.selected_mode >= mode_count {
+            self.reset_mode_cursor();
+        }
+    }
+
+    fn reset_mode_cursor(&mut self) {
+        self.selected_mode = self
+            .selected_output()
+            .and_then(|output| output.current_mode)
+            .unwrap_or(0);
+    }
+
+    fn log_selected_output(&mut self) {
+        if let Some(output) = self.selected_output() {
+            let line = format!(
+                "selected output: {} ({})",
+                output.name,
+                output.description()
+            );
+            self.log(line);
+        }
+    }
+
+    fn log_selected_mode(&mut self) {
+        if let Some(mode) = self
+            .selected_output()
+            .and_then(|output| output.modes.get(self.selected_mode))
+        {
+            let line = format!("selected mode: {}", mode.label());
+            self.log(line);
+        }
+    }
+}
@@ -0,0 +1,57 @@
+use crate::app::App;
+
+/// Which list in the Audio tab the selection and volume keys act on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AudioFocus {
+    #[default]
+    Sinks,
+    Sources,
+    Streams,
+    Cards,
+}
+
+impl App {
+    pub fn toggle_audio_focus(&mut self) {
+        self.audio_focus = match self.audio_focus {
+            AudioFocus::Sinks => AudioFocus::Sources,
+            AudioFocus::Sources => AudioFocus::Streams,
+            AudioFocus::Streams => AudioFocus::Cards,
+            AudioFocus::Cards => AudioFocus::Sinks,
+        };
+        if self.audio_focus == AudioFocus::Cards {
+            self.select_active_card_profile();
+        }
+    }
+
+    pub fn select_next_audio_source(&mut self) {
+        if self.audio_sources.is_empty() {
+            self.log("no audio sources discovered");
+            return;
+        }
+        self.selected_audio_source = (self.selected_audio_source + 1) % self.audio_sources.len();
+    }
+
+    pub fn select_prev_audio_source(&mut self) {
+        if self.audio_sources.is_empty() {
+            self.log("no audio sources discovered");
+            return;
+        }
+        self.selected_audio_source =
+            (self.selected_audio_source + self.audio_sources.len() - 1) % self.audio_sources.len();
+    }
+
+    pub fn apply_selected_audio_source(&mut self) {
+        let Some(source) = self.audio_sources.get(self.selected_audio_source).cloned() else {
+            self.log("no audio sources available to apply");
+            return;
+        };
+        match self.audio.set_default_by_id(&source.id) {
+            Ok(()) => self.log(format!(
+                "set default audio source: {}",
+                source.display_line()
+            )),
+            Err(err) => self.log(format!("failed to switch audio source: {err}")),
+        }
+        self.refresh_audio_devices();
+    }
+}
@@ -0,0 +1,57 @@
+use crate::app::App;
+
+impl App {
+    pub fn select_next_audio_stream(&mut self) {
+        if self.audio_streams.is_empty() {
+            self.log("no playback streams");
+            return;
+        }
+        self.selected_audio_stream = (self.selected_audio_stream + 1) % self.audio_streams.len();
+    }
+
+    pub fn select_prev_audio_stream(&mut self) {
+        if self.audio_streams.is_empty() {
+            self.log("no playback streams");
+            return;
+        }
+        self.selected_audio_stream =
+            (self.selected_audio_stream + self.audio_streams.len() - 1) % self.audio_streams.len();
+    }
+
+    /// Steps the destination sink shown for the selected stream; the sink
+    /// list cursor doubles as the destination.
+    pub fn cycle_stream_destination(&mut self, forward: bool) {
+        if self.audio_sinks.is_empty() {
+            self.log("no audio sinks discovered");
+            return;
+        }
+        let len = self.audio_sinks.len();
+        self.selected_audio_sink = if forward {
+            (self.selected_audio_sink + 1) % len
+        } else {
+            (self.selected_audio_sink + len - 1) % len
+        };
+    }
+
+    /// Moves only the selected stream to the destination sink, e.g. the
+    /// movie to the TV while a call stays on the laptop.
+    pub fn move_selected_stream(&mut self) {
+        let Some(stream) = self.audio_streams.get(self.selected_audio_stream).cloned() else {
+            self.log("no playback stream selected");
+            return;
+        };
+        let Some(sink) = self.audio_sinks.get(self.selected_audio_sink).cloned() else {
+            self.log("no audio sink to move the stream to");
+            return;
+        };
+        if stream.sink_id.as_deref() == Some(sink.id.as_str()) {
+            self.log(format!("{} already plays on {}", stream.label(), sink.name));
+            return;
+        }
+        match self.audio.move_stream(&stream, &sink) {
+            Ok(()) => self.log(format!("moved {} to {}", stream.label(), sink.name)),
+            Err(err) => self.log(format!("failed to move {}: {err}", stream.label())),
+        }
+        self.refresh_audio_devices();
+    }
+}
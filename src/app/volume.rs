@@ -0,0 +1,67 @@
+use crate::app::{App, AudioFocus};
+
+const VOLUME_STEP: i32 = 5;
+
+impl App {
+    /// Id, name and mute state of the selected sink or source, whichever
+    /// list has focus. Streams follow the volume of the sink they play on.
+    fn selected_volume_target(&self) -> Option<(String, String, bool)> {
+        match self.audio_focus {
+            AudioFocus::Sinks => self
+                .audio_sinks
+                .get(self.selected_audio_sink)
+                .map(|sink| (sink.id.clone(), sink.name.clone(), sink.muted)),
+            AudioFocus::Sources => self
+                .audio_sources
+                .get(self.selected_audio_source)
+                .map(|source| (source.id.clone(), source.name.clone(), source.muted)),
+            AudioFocus::Streams | AudioFocus::Cards => None,
+        }
+    }
+
+    pub fn raise_selected_volume(&mut self) {
+        self.step_selected_volume(VOLUME_STEP);
+    }
+
+    pub fn lower_selected_volume(&mut self) {
+        self.step_selected_volume(-VOLUME_STEP);
+    }
+
+    fn step_selected_volume(&mut self, percent: i32) {
+        let Some((id, name, _)) = self.selected_volume_target() else {
+            self.log("select a sink or source to change its volume");
+            return;
+        };
+        if let Err(err) = self.audio.step_volume(&id, percent) {
+            self.log(format!("failed to change volume of {name}: {err}"));
+        }
+        self.refresh_audio_devices();
+    }
+
+    pub fn set_selected_volume(&mut self, percent: u32) {
+        let Some((id, name, _)) = self.selected_volume_target() else {
+            self.log("select a sink or source to change its volume");
+            return;
+        };
+        match self.audio.set_volume(&id, percent) {
+            Ok(()) => self.log(format!("{name} volume: {percent}%")),
+            Err(err) => self.log(format!("failed to set volume of {name}: {err}")),
+        }
+        self.refresh_audio_devices();
+    }
+
+    pub fn toggle_selected_mute(&mut self) {
+        let Some((id, name, muted)) = self.selected_volume_target() else {
+            self.log("select a sink or source to change its volume");
+            return;
+        };
+        match self.audio.toggle_mute(&id) {
+            Ok(()) => self.log(format!(
+                "{name} {}",
+                if muted { "unmuted" } else { "muted" }
+            )),
+            Err(err) => self.log(format!("failed to toggle mute of {name}: {err}")),
+        }
+        self.refresh_audio_devices();
+    }
+}
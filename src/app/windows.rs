@@ -0,0 +1,86 @@
+use crate::app::App;
+
+impl App {
+    pub(super) fn refresh_windows(&mut self) {
+        match self.niri.windows() {
+            Ok(mut windows) => {
+                windows.sort_by_key(|w| (w.workspace_id, w.id));
+                self.windows = windows;
+            }
+            Err(err) => self.log(format!("failed to list windows: {err}")),
+        }
+        if let Ok(workspaces) = self.niri.workspaces() {
+            self.workspaces = workspaces;
+        }
+        if self.selected_window >= self.windows.len() {
+            self.selected_window = self.windows.len().saturating_sub(1);
+        }
+        self.forget_closed_dynamic_cast_window();
+    }
+
+    pub fn select_next_window(&mut self) {
+        if self.windows.is_empty() {
+            self.log("no windows open");
+            return;
+        }
+        self.selected_window = (self.selected_window + 1) % self.windows.len();
+    }
+
+    pub fn select_prev_window(&mut self) {
+        if self.windows.is_empty() {
+            self.log("no windows open");
+            return;
+        }
+        if self.selected_window == 0 {
+            self.selected_window = self.windows.len() - 1;
+        } else {
+            self.selected_window -= 1;
+        }
+    }
+
+    pub fn send_selected_window(&mut self) {
+        self.send_selected_window_to_target(false);
+    }
+
+    pub fn send_selected_window_fullscreen(&mut self) {
+        self.send_selected_window_to_target(true);
+    }
+
+    fn send_selected_window_to_target(&mut self, fullscreen: bool) {
+        let Some(window) = self.windows.get(self.selected_window).cloned() else {
+            self.log("no window selected");
+            return;
+        };
+        let Some(target) = self.cast_target_name() else {
+            self.log("no external output to send the window to");
+            return;
+        };
+        let enabled = self
+            .outputs
+            .iter()
+            .any(|o| o.name == target && o.logical.is_some());
+        if !enabled {
+            self.log(format!("{target} is off; start a cast mode first"));
+            return;
+        }
+
+        let result = self
+            .niri
+            .move_window_to_monitor(window.id, &target)
+            .and_then(|()| {
+                if fullscreen {
+                    self.niri.toggle_fullscreen_window(window.id)?;
+                }
+                Ok(())
+            });
+        match result {
+            Ok(()) if fullscreen => self.log(format!(
+                "sent {} to {target} and toggled fullscreen",
+                window.label()
+            )),
+            Ok(()) => self.log(format!("sent {} to {target}", window.label())),
+            Err(err) => self.log(format!("failed to send {}: {err}", window.label())),
+        }
+        self.refresh_windows();
+    }
+}
@@ -0,0 +1,221 @@
+use std::io::{self, BufRead, Write};
+
+use anyhow::{anyhow, bail, Context};
+
+use crate::adapters::niri::{NiriAdapter, NiriTransform};
+use crate::config::ConfigStore;
+use crate::core::niri_config::{KdlExport, NiriConfigFile};
+use crate::core::snapshot::LayoutSnapshot;
+use crate::profiles::ProfileStore;
+
+const USAGE: &str = "\
+usage: niri-cast [--revert-timeout <secs>]         start the TUI
+                                                   (0 disables layout auto-revert)
+       niri-cast output <name> scale <factor>      set fractional scale
+       niri-cast output <name> transform <value>   set rotation/flip
+                                                   (normal, 90, 180, 270, flipped,
+                                                    flipped-90, flipped-180, flipped-270)
+       niri-cast export-kdl [<profile>]            write the live layout (or a saved
+                                                   profile's) into niri's config.kdl";
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TuiOptions {
+    pub revert_timeout_secs: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CliCommand {
+    Tui(TuiOptions),
+    Help,
+    SetScale {
+        output: String,
+        scale: f64,
+    },
+    SetTransform {
+        output: String,
+        transform: NiriTransform,
+    },
+    ExportKdl {
+        profile: Option<String>,
+    },
+}
+
+pub fn parse_args(args: &[String]) -> anyhow::Result<CliCommand> {
+    let args = args.iter().map(String::as_str).collect::<Vec<_>>();
+    match args.as_slice() {
+        [] => Ok(CliCommand::Tui(TuiOptions::default())),
+        ["-h" | "--help" | "help"] => Ok(CliCommand::Help),
+        ["--revert-timeout", value] => {
+            let secs = value
+                .parse::<u64>()
+                .with_context(|| format!("invalid revert timeout: {value}"))?;
+            Ok(CliCommand::Tui(TuiOptions {
+                revert_timeout_secs: Some(secs),
+            }))
+        }
+        ["output", output, "scale", value] => {
+            let scale = value
+                .parse::<f64>()
+                .with_context(|| format!("invalid scale: {value}"))?;
+            Ok(CliCommand::SetScale {
+                output: output.to_string(),
+                scale,
+            })
+        }
+        ["output", output, "transform", value] => {
+            let transform = NiriTransform::from_name(value)
+                .ok_or_else(|| anyhow!("invalid transform: {value}"))?;
+            Ok(CliCommand::SetTransform {
+                output: output.to_string(),
+                transform,
+            })
+        }
+        ["export-kdl"] => Ok(CliCommand::ExportKdl { profile: None }),
+        ["export-kdl", profile] => Ok(CliCommand::ExportKdl {
+            profile: Some(profile.to_string()),
+        }),
+        _ => bail!("unrecognized arguments: {}\n{USAGE}", args.join(" ")),
+    }
+}
+
+pub fn run_command(command: CliCommand) -> anyhow::Result<()> {
+    let niri = NiriAdapter::from_env();
+    match command {
+        CliCommand::Tui(options) => {
+            let mut config = ConfigStore::new()?.load()?;
+            if let Some(secs) = options.revert_timeout_secs {
+                config.revert_timeout_secs = secs;
+            }
+            crate::app::run(config)
+        }
+        CliCommand::Help => {
+            println!("{USAGE}");
+            Ok(())
+        }
+        CliCommand::SetScale { output, scale } => {
+            niri.set_scale(&output, scale)?;
+            print_output_size(&niri, &output);
+            Ok(())
+        }
+        CliCommand::SetTransform { output, transform } => {
+            niri.set_transform(&output, transform)?;
+            print_output_size(&niri, &output);
+            Ok(())
+        }
+        CliCommand::ExportKdl { profile } => export_kdl(&niri, profile.as_deref()),
+    }
+}
+
+fn export_kdl(niri: &NiriAdapter, profile: Option<&str>) -> anyhow::Result<()> {
+    let (source, layout) = match profile {
+        Some(name) => {
+            let profile = ProfileStore::new()?
+                .load_profile(name)?
+                .ok_or_else(|| anyhow!("profile {name} not found"))?;
+            let layout = profile
+                .layout
+                .ok_or_else(|| anyhow!("profile {name} has no saved layout"))?;
+            (format!("profile {name}"), layout)
+        }
+        None => (
+            "live layout".to_string(),
+            LayoutSnapshot::capture(&niri.outputs_json()?),
+        ),
+    };
+
+    let export = KdlExport::prepare(NiriConfigFile::locate()?, source, &layout.outputs)?;
+    let path = export.file.path().display().to_string();
+    if export.is_noop() {
+        println!("{path} already matches the {}", export.source);
+        return Ok(());
+    }
+    for line in &export.diff {
+        println!("{line}");
+    }
+    print!("write these changes to {path}? [y/N] ");
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    io::stdin().lock().read_line(&mut answer)?;
+    if !answer.trim().eq_ignore_ascii_case("y") {
+        println!("nothing written");
+        return Ok(());
+    }
+    match export.file.write_with_backup(&export.content)? {
+        Some(backup) => println!("wrote {path} (backup: {})", backup.display()),
+        None => println!("created {path}"),
+    }
+    Ok(())
+}
+
+fn print_output_size(niri: &NiriAdapter, output_name: &str) {
+    let line = niri
+        .outputs_json()
+        .ok()
+        .and_then(|outputs| outputs.into_iter().find(|o| o.name == output_name))
+        .map(|output| output.summary_line())
+        .unwrap_or_else(|| format!("{output_name}: updated"));
+    println!("{line}");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_args, CliCommand, TuiOptions};
+    use crate::adapters::niri::NiriTransform;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn no_arguments_start_the_tui() {
+        assert_eq!(
+            parse_args(&[]).unwrap(),
+            CliCommand::Tui(TuiOptions::default())
+        );
+    }
+
+    #[test]
+    fn parses_revert_timeout_flag() {
+        assert_eq!(
+            parse_args(&args(&["--revert-timeout", "30"])).unwrap(),
+            CliCommand::Tui(TuiOptions {
+                revert_timeout_secs: Some(30)
+            })
+        );
+        assert!(parse_args(&args(&["--revert-timeout", "soon"])).is_err());
+    }
+
+    #[test]
+    fn parses_output_commands() {
+        assert_eq!(
+            parse_args(&args(&["output", "HDMI-A-1", "scale", "2"])).unwrap(),
+            CliCommand::SetScale {
+                output: "HDMI-A-1".to_string(),
+                scale: 2.0
+            }
+        );
+        assert_eq!(
+            parse_args(&args(&["output", "eDP-1", "transform", "flipped-90"])).unwrap(),
+            CliCommand::SetTransform {
+                output: "eDP-1".to_string(),
+                transform: NiriTransform::Flipped90
+            }
+        );
+        assert!(parse_args(&args(&["output", "eDP-1", "transform", "sideways"])).is_err());
+        assert!(parse_args(&args(&["output", "eDP-1", "scale"])).is_err());
+    }
+
+    #[test]
+    fn parses_export_kdl() {
+        assert_eq!(
+            parse_args(&args(&["export-kdl"])).unwrap(),
+            CliCommand::ExportKdl { profile: None }
+        );
+        assert_eq!(
+            parse_args(&args(&["export-kdl", "living-room"])).unwrap(),
+            CliCommand::ExportKdl {
+                profile: Some("living-room".to_string())
+            }
+        );
+    }
+}
@@ -0,0 +1,5 @@
+mod model;
+mod store;
+
+pub use model::{AppConfig, AudioRule, RuleSink};
+pub use store::ConfigStore;
@@ -0,0 +1,42 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppConfig {
+    /// Seconds before an unconfirmed layout change is reverted; 0 disables
+    /// the confirmation prompt.
+    pub revert_timeout_secs: u64,
+    /// Switch to the cast output's HDMI sink when a cast mode starts and
+    /// back when casting stops.
+    pub audio_follows_display: bool,
+    /// Where matching applications play while casting; streams without a
+    /// rule follow the default sink.
+    pub audio_rules: Vec<AudioRule>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AudioRule {
+    /// Compared case-insensitively with the stream's `application.name`
+    /// and process binary, e.g. `mpv` or `Discord`.
+    pub application: String,
+    pub sink: RuleSink,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RuleSink {
+    /// The cast output's HDMI sink.
+    Tv,
+    /// The first non-HDMI sink.
+    Laptop,
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        Self {
+            revert_timeout_secs: 15,
+            audio_follows_display: true,
+            audio_rules: Vec::new(),
+        }
+    }
+}
@@ -0,0 +1,35 @@
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::Context;
+
+use crate::config::model::AppConfig;
+
+#[derive(Debug, Clone)]
+pub struct ConfigStore {
+    path: PathBuf,
+}
+
+impl ConfigStore {
+    pub fn new() -> anyhow::Result<Self> {
+        let mut dir = dirs::config_dir().context("could not resolve XDG config dir")?;
+        dir.push("niri-cast");
+        fs::create_dir_all(&dir).context("failed to create config directory")?;
+
+        let mut path = dir;
+        path.push("config.json");
+
+        if !path.exists() {
+            let initial = serde_json::to_string_pretty(&AppConfig::default())?;
+            fs::write(&path, initial).context("failed to initialize config")?;
+        }
+
+        Ok(Self { path })
+    }
+
+    pub fn load(&self) -> anyhow::Result<AppConfig> {
+        let content = fs::read_to_string(&self.path).context("failed to read config")?;
+        let parsed = serde_json::from_str(&content).context("failed to parse config.json")?;
+        Ok(parsed)
+    }
+}
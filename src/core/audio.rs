@@ -1,5 +1,179 @@
-use crate::adapters::audio::AudioAdapter;
+use crate::adapters::audio::{AudioAdapter, AudioCard, AudioSink, CardProfile};
+use crate::adapters::pipewire::PwAvailability;
 
-pub fn switch_to_hdmi(audio: &AudioAdapter) -> anyhow::Result<Option<String>> {
-    audio.select_first_hdmi_sink()
+/// `alsa_output.pci-0000_01_00.1.hdmi-stereo` -> `0000:01:00.1`.
+pub fn pci_address_from_node_name(node_name: &str) -> Option<String> {
+    let start = node_name.find("pci-")? + "pci-".len();
+    let raw = node_name.get(start..start + 12)?;
+    let bytes = raw.as_bytes();
+    if !(raw.is_ascii() && bytes[4] == b'_' && bytes[7] == b'_' && bytes[10] == b'.') {
+        return None;
+    }
+    Some(format!("{}:{}:{}", &raw[..4], &raw[5..7], &raw[8..]))
+}
+
+/// Discrete GPUs expose HDMI audio as another function of the same PCI
+/// slot (`01:00.0` video, `01:00.1` audio), so the function is ignored.
+pub fn same_pci_slot(a: &str, b: &str) -> bool {
+    match (a.rsplit_once('.'), b.rsplit_once('.')) {
+        (Some((a, _)), Some((b, _))) => a == b,
+        _ => false,
+    }
+}
+
+/// Picks the HDMI/DisplayPort sink for an output: one on the output's GPU
+/// when it can be told apart, otherwise the first external sink (integrated
+/// GPUs route HDMI audio through the shared HDA controller).
+pub fn choose_sink_for_gpu<'a>(
+    sinks: &'a [AudioSink],
+    gpu_pci_address: Option<&str>,
+) -> Option<&'a AudioSink> {
+    gpu_pci_address
+        .and_then(|gpu| hdmi_sink_on_gpu(sinks, gpu))
+        .or_else(|| sinks.iter().find(|sink| sink.is_hdmi))
+}
+
+pub fn hdmi_sink_on_gpu<'a>(sinks: &'a [AudioSink], gpu_pci_address: &str) -> Option<&'a AudioSink> {
+    sinks.iter().filter(|sink| sink.is_hdmi).find(|sink| {
+        pci_address_from_node_name(&sink.node_name)
+            .is_some_and(|address| same_pci_slot(&address, gpu_pci_address))
+    })
+}
+
+pub fn card_on_gpu(card: &AudioCard, gpu_pci_address: &str) -> bool {
+    pci_address_from_node_name(&card.device_name)
+        .is_some_and(|address| same_pci_slot(&address, gpu_pci_address))
+}
+
+/// Which kind of card profile a switch is looking for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProfileTarget {
+    Tv,
+    Laptop,
+}
+
+impl ProfileTarget {
+    /// ALSA names DisplayPort profiles `hdmi` too; duplex profiles such as
+    /// `output:hdmi-stereo+input:analog-stereo` match by their output part.
+    fn matches(self, profile: &CardProfile) -> bool {
+        let output = match self {
+            ProfileTarget::Tv => "output:hdmi",
+            ProfileTarget::Laptop => "output:analog",
+        };
+        profile.name.split('+').any(|part| part.starts_with(output))
+    }
+}
+
+/// Picks the card and profile to switch to. Profiles the card reports as
+/// unavailable are skipped; among the rest a card on the output's GPU wins,
+/// then a profile with a plugged port, then the card's own priority. That is
+/// how `hdmi-stereo-extra1` is chosen when the TV sits on the second
+/// connector.
+pub fn choose_card_profile<'a>(
+    cards: &'a [AudioCard],
+    target: ProfileTarget,
+    gpu_pci_address: Option<&str>,
+) -> Option<(&'a AudioCard, &'a CardProfile)> {
+    cards
+        .iter()
+        .flat_map(|card| card.profiles.iter().map(move |profile| (card, profile)))
+        .filter(|(_, profile)| target.matches(profile) && profile.available != PwAvailability::No)
+        .max_by_key(|(card, profile)| {
+            (
+                gpu_pci_address.is_some_and(|gpu| card_on_gpu(card, gpu)),
+                profile
+                    .ports
+                    .iter()
+                    .any(|port| port.plugged == PwAvailability::Yes),
+                profile.available == PwAvailability::Yes,
+                profile.priority,
+            )
+        })
+}
+
+/// Switches the best matching card among `cards` to its profile for
+/// `target`. Returns what was switched, or `None` when nothing fits or the
+/// profile is already active.
+pub fn switch_card_profile(
+    audio: &AudioAdapter,
+    cards: &[AudioCard],
+    target: ProfileTarget,
+    gpu_pci_address: Option<&str>,
+) -> anyhow::Result<Option<String>> {
+    let Some((card, profile)) = choose_card_profile(cards, target, gpu_pci_address) else {
+        return Ok(None);
+    };
+    if card.active_profile == Some(profile.index) {
+        return Ok(None);
+    }
+    audio.set_card_profile(&card.id, profile.index)?;
+    Ok(Some(format!("{}: {}", card.name, profile.description)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        choose_card_profile, choose_sink_for_gpu, pci_address_from_node_name, same_pci_slot,
+        ProfileTarget,
+    };
+    use crate::adapters::audio::fixtures::sink;
+    use crate::adapters::audio::{cards_from_dump, AudioCard};
+    use crate::adapters::pipewire::parse_pw_dump;
+
+    #[test]
+    fn parses_pci_address_from_node_name() {
+        assert_eq!(
+            pci_address_from_node_name("alsa_output.pci-0000_01_00.1.hdmi-stereo-extra1"),
+            Some("0000:01:00.1".to_string())
+        );
+        assert_eq!(pci_address_from_node_name("bluez_output.AA_BB.1"), None);
+        assert_eq!(pci_address_from_node_name("alsa_output.pci-0000é01_00.1"), None);
+        assert_eq!(pci_address_from_node_name("alsa_output.pci-000é_01_00.1"), None);
+        assert!(same_pci_slot("0000:01:00.1", "0000:01:00.0"));
+        assert!(!same_pci_slot("0000:00:1f.3", "0000:00:02.0"));
+    }
+
+    #[test]
+    fn prefers_sink_on_the_outputs_gpu() {
+        let sinks = vec![
+            sink("40", "alsa_output.pci-0000_00_1f.3.hdmi-stereo", false, true),
+            sink("41", "alsa_output.pci-0000_01_00.1.hdmi-stereo", false, true),
+            sink("42", "alsa_output.pci-0000_00_1f.3.analog-stereo", false, false),
+        ];
+        assert_eq!(choose_sink_for_gpu(&sinks, Some("0000:01:00.0")).unwrap().id, "41");
+        assert_eq!(choose_sink_for_gpu(&sinks, Some("0000:00:02.0")).unwrap().id, "40");
+        assert_eq!(choose_sink_for_gpu(&sinks, None).unwrap().id, "40");
+    }
+
+    #[test]
+    fn picks_plugged_hdmi_profile_on_the_outputs_gpu() {
+        let dump = parse_pw_dump(include_str!(
+            "../../tests/fixtures/pw-dump-laptop-nvidia-hdmi.json"
+        ))
+        .unwrap();
+        let mut cards = cards_from_dump(&dump);
+        fn chosen(
+            cards: &[AudioCard],
+            target: ProfileTarget,
+            gpu: Option<&str>,
+        ) -> Option<(String, String)> {
+            choose_card_profile(cards, target, gpu)
+                .map(|(card, profile)| (card.id.clone(), profile.name.clone()))
+        }
+        let nvidia_hdmi2 = Some(("55".to_string(), "output:hdmi-stereo-extra1".to_string()));
+        assert_eq!(chosen(&cards, ProfileTarget::Tv, Some("0000:01:00.0")), nvidia_hdmi2);
+        // The Intel HDMI profiles are unavailable, so the NVIDIA card wins
+        // even without knowing the GPU.
+        assert_eq!(chosen(&cards, ProfileTarget::Tv, None), nvidia_hdmi2);
+        assert_eq!(
+            chosen(&cards, ProfileTarget::Laptop, None),
+            Some((
+                "42".to_string(),
+                "output:analog-stereo+input:analog-stereo".to_string()
+            ))
+        );
+
+        cards.retain(|card| card.id == "42");
+        assert_eq!(chosen(&cards, ProfileTarget::Tv, Some("0000:01:00.0")), None);
+    }
 }
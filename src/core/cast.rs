@@ -16,10 +16,27 @@ impl CastPreflight {
     }
 }
 
+/// What niri's dynamic screencast target was last set to from niri-cast.
+/// niri cannot be asked for it, so this is tracked on our side.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DynamicCastTarget {
+    Window { id: u64, label: String },
+    Monitor(String),
+}
+
+impl DynamicCastTarget {
+    pub fn describe(&self) -> String {
+        match self {
+            DynamicCastTarget::Window { label, .. } => format!("window {label}"),
+            DynamicCastTarget::Monitor(output) => format!("output {output}"),
+        }
+    }
+}
+
 pub fn preflight(system: &SystemAdapter, portal: &PortalAdapter) -> CastPreflight {
     let mut missing = Vec::new();
 
-    for cmd in ["niri", "wpctl", "wl-mirror"] {
+    for cmd in ["niri", "wpctl", "pw-dump", "wl-mirror"] {
         if !system.command_exists(cmd) {
             missing.push(format!("missing command: {cmd}"));
         }
@@ -0,0 +1,366 @@
+use crate::adapters::niri::{NiriLogical, NiriOutput};
+use crate::core::monitors::choose_primary_output;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LayoutCastMode {
+    ExtendRight,
+    ExtendLeft,
+    ExtendAbove,
+    ExtendBelow,
+    Mirror,
+    HdmiOnly,
+}
+
+impl LayoutCastMode {
+    pub const ALL: [LayoutCastMode; 6] = [
+        LayoutCastMode::ExtendRight,
+        LayoutCastMode::ExtendLeft,
+        LayoutCastMode::ExtendAbove,
+        LayoutCastMode::ExtendBelow,
+        LayoutCastMode::Mirror,
+        LayoutCastMode::HdmiOnly,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            LayoutCastMode::ExtendRight => "extend-right",
+            LayoutCastMode::ExtendLeft => "extend-left",
+            LayoutCastMode::ExtendAbove => "extend-above",
+            LayoutCastMode::ExtendBelow => "extend-below",
+            LayoutCastMode::Mirror => "mirror",
+            LayoutCastMode::HdmiOnly => "hdmi-only",
+        }
+    }
+
+    pub fn extend_side(self) -> Option<Side> {
+        match self {
+            LayoutCastMode::ExtendRight => Some(Side::Right),
+            LayoutCastMode::ExtendLeft => Some(Side::Left),
+            LayoutCastMode::ExtendAbove => Some(Side::Above),
+            LayoutCastMode::ExtendBelow => Some(Side::Below),
+            LayoutCastMode::Mirror | LayoutCastMode::HdmiOnly => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Right,
+    Left,
+    Above,
+    Below,
+}
+
+impl Side {
+    pub fn relation(self) -> &'static str {
+        match self {
+            Side::Right => "right of",
+            Side::Left => "left of",
+            Side::Above => "above",
+            Side::Below => "below",
+        }
+    }
+}
+
+/// Where the external output sits along the shared edge: top/left for
+/// `Start`, bottom/right for `End`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Alignment {
+    #[default]
+    Start,
+    Center,
+    End,
+}
+
+impl Alignment {
+    pub fn next(self) -> Self {
+        match self {
+            Alignment::Start => Alignment::Center,
+            Alignment::Center => Alignment::End,
+            Alignment::End => Alignment::Start,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Alignment::Start => "start",
+            Alignment::Center => "center",
+            Alignment::End => "end",
+        }
+    }
+
+    fn offset(self, start: i32, span: i32, len: i32) -> i32 {
+        match self {
+            Alignment::Start => start,
+            Alignment::Center => start + (span - len) / 2,
+            Alignment::End => start + span - len,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rect {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+impl From<&NiriLogical> for Rect {
+    fn from(logical: &NiriLogical) -> Self {
+        Self {
+            x: logical.x,
+            y: logical.y,
+            width: logical.width,
+            height: logical.height,
+        }
+    }
+}
+
+/// Top-left position for an output of `size` placed on `side` of `primary`.
+pub fn extend_position(
+    primary: Rect,
+    size: (i32, i32),
+    side: Side,
+    alignment: Alignment,
+) -> (i32, i32) {
+    let (width, height) = size;
+    match side {
+        Side::Right => (
+            primary.x + primary.width,
+            alignment.offset(primary.y, primary.height, height),
+        ),
+        Side::Left => (
+            primary.x - width,
+            alignment.offset(primary.y, primary.height, height),
+        ),
+        Side::Above => (
+            alignment.offset(primary.x, primary.width, width),
+            primary.y - height,
+        ),
+        Side::Below => (
+            alignment.offset(primary.x, primary.width, width),
+            primary.y + primary.height,
+        ),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlacedOutput {
+    pub name: String,
+    pub rect: Rect,
+    pub is_target: bool,
+}
+
+/// Enabled outputs as they are laid out right now.
+pub fn current_layout(outputs: &[NiriOutput], target: Option<&str>) -> Vec<PlacedOutput> {
+    outputs
+        .iter()
+        .filter_map(|output| {
+            let logical = output.logical.as_ref()?;
+            Some(PlacedOutput {
+                name: output.name.clone(),
+                rect: Rect::from(logical),
+                is_target: target == Some(output.name.as_str()),
+            })
+        })
+        .collect()
+}
+
+/// The layout `mode` would produce, computed with the same placement rules
+/// as applying it, without touching niri.
+pub fn project_layout(
+    outputs: &[NiriOutput],
+    mode: LayoutCastMode,
+    target: &str,
+    alignment: Alignment,
+) -> Vec<PlacedOutput> {
+    let mut placed = current_layout(outputs, Some(target));
+    let Some(target_output) = outputs.iter().find(|o| o.name == target) else {
+        return placed;
+    };
+    let Some((width, height)) = target_output.logical_size() else {
+        return placed;
+    };
+
+    let current_rect = placed.iter().find(|p| p.is_target).map(|p| p.rect);
+    let primary_rect = choose_primary_output(outputs, target)
+        .and_then(|o| o.logical.as_ref())
+        .map(Rect::from);
+
+    let rect = match (mode.extend_side(), primary_rect) {
+        (Some(side), Some(primary)) => {
+            let (x, y) = extend_position(primary, (width, height), side, alignment);
+            Rect {
+                x,
+                y,
+                width,
+                height,
+            }
+        }
+        _ => current_rect.unwrap_or_else(|| {
+            // niri places a newly enabled output to the right of the others.
+            let right_edge = placed
+                .iter()
+                .map(|p| p.rect.x + p.rect.width)
+                .max()
+                .unwrap_or(0);
+            Rect {
+                x: right_edge,
+                y: 0,
+                width,
+                height,
+            }
+        }),
+    };
+
+    placed.retain(|p| !p.is_target);
+    if mode == LayoutCastMode::HdmiOnly {
+        placed.clear();
+    }
+    placed.push(PlacedOutput {
+        name: target.to_string(),
+        rect,
+        is_target: true,
+    });
+    placed
+}
+
+/// Moves `rect` so an edge lines up with a neighbour's edge when it is
+/// within `threshold` logical pixels of one. Each axis snaps independently.
+pub fn snap_position(rect: Rect, others: &[Rect], threshold: i32) -> (i32, i32) {
+    let x = snap_axis(
+        rect.x,
+        rect.width,
+        others.iter().flat_map(|o| [o.x, o.x + o.width]),
+        threshold,
+    );
+    let y = snap_axis(
+        rect.y,
+        rect.height,
+        others.iter().flat_map(|o| [o.y, o.y + o.height]),
+        threshold,
+    );
+    (x, y)
+}
+
+fn snap_axis(start: i32, length: i32, edges: impl Iterator<Item = i32>, threshold: i32) -> i32 {
+    edges
+        .flat_map(|edge| [edge, edge - length])
+        .map(|candidate| (candidate, (candidate - start).abs()))
+        .filter(|(_, distance)| *distance <= threshold)
+        .min_by_key(|(_, distance)| *distance)
+        .map(|(candidate, _)| candidate)
+        .unwrap_or(start)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        current_layout, extend_position, project_layout, snap_position, Alignment,
+        LayoutCastMode, Rect, Side,
+    };
+    use crate::adapters::niri::fixtures::output;
+
+    const PANEL: Rect = Rect {
+        x: 0,
+        y: 0,
+        width: 1920,
+        height: 1200,
+    };
+    const TV: (i32, i32) = (1280, 720);
+
+    #[test]
+    fn places_beside_with_vertical_alignment() {
+        assert_eq!(extend_position(PANEL, TV, Side::Right, Alignment::Start), (1920, 0));
+        assert_eq!(extend_position(PANEL, TV, Side::Right, Alignment::Center), (1920, 240));
+        assert_eq!(extend_position(PANEL, TV, Side::Left, Alignment::End), (-1280, 480));
+    }
+
+    #[test]
+    fn places_above_and_below_with_horizontal_alignment() {
+        assert_eq!(extend_position(PANEL, TV, Side::Above, Alignment::Start), (0, -720));
+        assert_eq!(extend_position(PANEL, TV, Side::Above, Alignment::Center), (320, -720));
+        assert_eq!(extend_position(PANEL, TV, Side::Below, Alignment::End), (640, 1200));
+    }
+
+    #[test]
+    fn cycles_alignment() {
+        assert_eq!(Alignment::Start.next(), Alignment::Center);
+        assert_eq!(Alignment::End.next(), Alignment::Start);
+    }
+
+    #[test]
+    fn current_layout_skips_disabled_outputs() {
+        let outputs = vec![
+            output("eDP-1", Some((0, 0, 1920, 1200))),
+            output("HDMI-A-1", None),
+        ];
+        let placed = current_layout(&outputs, Some("HDMI-A-1"));
+        assert_eq!(placed.len(), 1);
+        assert!(!placed[0].is_target);
+    }
+
+    #[test]
+    fn projects_extend_modes_for_disabled_target() {
+        let outputs = vec![
+            output("eDP-1", Some((0, 0, 1920, 1200))),
+            output("HDMI-A-1", None),
+        ];
+        let placed = project_layout(
+            &outputs,
+            LayoutCastMode::ExtendAbove,
+            "HDMI-A-1",
+            Alignment::Center,
+        );
+        assert_eq!(placed.len(), 2);
+        let tv = placed.iter().find(|p| p.is_target).unwrap();
+        assert_eq!(
+            tv.rect,
+            Rect {
+                x: 0,
+                y: -1080,
+                width: 1920,
+                height: 1080
+            }
+        );
+    }
+
+    #[test]
+    fn projects_hdmi_only_as_target_alone() {
+        let outputs = vec![
+            output("eDP-1", Some((0, 0, 1920, 1200))),
+            output("HDMI-A-1", Some((1920, 0, 1920, 1080))),
+        ];
+        let placed = project_layout(
+            &outputs,
+            LayoutCastMode::HdmiOnly,
+            "HDMI-A-1",
+            Alignment::Start,
+        );
+        assert_eq!(placed.len(), 1);
+        assert_eq!(placed[0].name, "HDMI-A-1");
+    }
+
+    #[test]
+    fn snaps_to_nearby_edges_only() {
+        let laptop = Rect {
+            x: 0,
+            y: 0,
+            width: 1920,
+            height: 1200,
+        };
+        let tv = Rect {
+            x: 1950,
+            y: -30,
+            width: 1920,
+            height: 1080,
+        };
+        assert_eq!(snap_position(tv, &[laptop], 50), (1920, 0));
+        assert_eq!(snap_position(tv, &[laptop], 10), (1950, -30));
+
+        // The bottom edge lines up with the laptop's bottom edge.
+        let below = Rect { y: 140, ..tv };
+        assert_eq!(snap_position(below, &[laptop], 50), (1920, 120));
+    }
+}
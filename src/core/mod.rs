@@ -1,4 +1,10 @@
 pub mod audio;
 pub mod cast;
+pub mod layout;
 pub mod monitors;
+pub mod niri_config;
 pub mod orchestrator;
+pub mod routing;
+pub mod snapshot;
+pub mod transaction;
+pub mod validation;
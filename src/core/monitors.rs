@@ -1,5 +1,97 @@
-use crate::adapters::niri::NiriAdapter;
+use crate::adapters::niri::NiriOutput;
 
-pub fn list_hdmi_outputs(niri: &NiriAdapter) -> anyhow::Result<Vec<String>> {
-    niri.list_hdmi_outputs()
+pub fn choose_cast_target<'a>(
+    outputs: &'a [NiriOutput],
+    preferred: Option<&str>,
+) -> Option<&'a NiriOutput> {
+    preferred
+        .and_then(|name| outputs.iter().find(|o| o.name == name))
+        .or_else(|| outputs.iter().find(|o| !o.is_internal()))
+}
+
+/// The output a cast target is placed against or mirrors: an enabled
+/// internal panel first, then any other enabled output, then disabled ones.
+pub fn choose_primary_output<'a>(
+    outputs: &'a [NiriOutput],
+    target: &str,
+) -> Option<&'a NiriOutput> {
+    let candidates = || outputs.iter().filter(move |o| o.name != target);
+    candidates()
+        .find(|o| o.is_internal() && o.logical.is_some())
+        .or_else(|| candidates().find(|o| o.logical.is_some()))
+        .or_else(|| candidates().find(|o| o.is_internal()))
+        .or_else(|| candidates().next())
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct OutputChanges {
+    pub connected: Vec<String>,
+    pub disconnected: Vec<String>,
+}
+
+impl OutputChanges {
+    pub fn is_empty(&self) -> bool {
+        self.connected.is_empty() && self.disconnected.is_empty()
+    }
+}
+
+pub fn diff_output_names(before: &[String], after: &[String]) -> OutputChanges {
+    OutputChanges {
+        connected: after
+            .iter()
+            .filter(|name| !before.contains(name))
+            .cloned()
+            .collect(),
+        disconnected: before
+            .iter()
+            .filter(|name| !after.contains(name))
+            .cloned()
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{choose_cast_target, choose_primary_output, diff_output_names};
+    use crate::adapters::niri::fixtures::output;
+
+    const ON: Option<(i32, i32, i32, i32)> = Some((0, 0, 1920, 1080));
+
+    #[test]
+    fn targets_first_external_output_unless_chosen() {
+        let outputs = vec![output("DP-1", ON), output("HDMI-A-1", None), output("eDP-1", ON)];
+        assert_eq!(choose_cast_target(&outputs, None).unwrap().name, "DP-1");
+        assert_eq!(
+            choose_cast_target(&outputs, Some("HDMI-A-1")).unwrap().name,
+            "HDMI-A-1"
+        );
+        assert_eq!(choose_cast_target(&outputs, Some("DP-9")).unwrap().name, "DP-1");
+        assert!(choose_cast_target(&[output("eDP-1", ON)], None).is_none());
+    }
+
+    #[test]
+    fn prefers_enabled_internal_panel_as_primary() {
+        let outputs = vec![output("DP-1", ON), output("eDP-1", ON), output("HDMI-A-1", ON)];
+        assert_eq!(choose_primary_output(&outputs, "HDMI-A-1").unwrap().name, "eDP-1");
+
+        let docked = vec![output("DP-1", ON), output("eDP-1", None), output("DP-2", ON)];
+        assert_eq!(choose_primary_output(&docked, "DP-2").unwrap().name, "DP-1");
+    }
+
+    fn names(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn detects_plugged_and_unplugged_outputs() {
+        let changes = diff_output_names(&names(&["eDP-1", "DP-2"]), &names(&["eDP-1", "HDMI-A-1"]));
+        assert_eq!(changes.connected, names(&["HDMI-A-1"]));
+        assert_eq!(changes.disconnected, names(&["DP-2"]));
+    }
+
+    #[test]
+    fn unchanged_outputs_are_empty() {
+        let outputs = names(&["eDP-1", "HDMI-A-1"]);
+        assert!(diff_output_names(&outputs, &outputs).is_empty());
+    }
 }
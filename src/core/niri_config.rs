@@ -0,0 +1,561 @@
+use std::fs;
+use std::ops::Range;
+use std::path::PathBuf;
+
+use anyhow::Context;
+
+use crate::core::snapshot::OutputSnapshot;
+
+/// niri's own config file; `$NIRI_CONFIG` wins over the XDG location, the
+/// same way niri resolves it.
+#[derive(Debug, Clone)]
+pub struct NiriConfigFile {
+    path: PathBuf,
+}
+
+impl NiriConfigFile {
+    pub fn locate() -> anyhow::Result<Self> {
+        if let Some(path) = std::env::var_os("NIRI_CONFIG").filter(|p| !p.is_empty()) {
+            return Ok(Self { path: path.into() });
+        }
+        let mut path = dirs::config_dir().context("could not resolve XDG config dir")?;
+        path.push("niri");
+        path.push("config.kdl");
+        Ok(Self { path })
+    }
+
+    pub fn path(&self) -> &PathBuf {
+        &self.path
+    }
+
+    pub fn read(&self) -> anyhow::Result<String> {
+        if !self.path.exists() {
+            return Ok(String::new());
+        }
+        fs::read_to_string(&self.path)
+            .with_context(|| format!("failed to read {}", self.path.display()))
+    }
+
+    /// Copies the current file next to itself before overwriting it and
+    /// returns where the backup went.
+    pub fn write_with_backup(&self, content: &str) -> anyhow::Result<Option<PathBuf>> {
+        let backup = if self.path.exists() {
+            let mut backup = self.path.clone().into_os_string();
+            backup.push(".niri-cast.bak");
+            let backup = PathBuf::from(backup);
+            fs::copy(&self.path, &backup)
+                .with_context(|| format!("failed to back up {}", self.path.display()))?;
+            Some(backup)
+        } else {
+            if let Some(dir) = self.path.parent() {
+                fs::create_dir_all(dir).context("failed to create niri config directory")?;
+            }
+            None
+        };
+        fs::write(&self.path, content)
+            .with_context(|| format!("failed to write {}", self.path.display()))?;
+        Ok(backup)
+    }
+}
+
+/// A config.kdl rewrite waiting for the user to look at the diff.
+#[derive(Debug, Clone)]
+pub struct KdlExport {
+    pub source: String,
+    pub file: NiriConfigFile,
+    pub content: String,
+    pub diff: Vec<String>,
+}
+
+impl KdlExport {
+    pub fn prepare(
+        file: NiriConfigFile,
+        source: impl Into<String>,
+        outputs: &[OutputSnapshot],
+    ) -> anyhow::Result<Self> {
+        let current = file.read()?;
+        let content = update_output_blocks(&current, outputs);
+        let diff = diff_lines(&current, &content);
+        Ok(Self {
+            source: source.into(),
+            file,
+            content,
+            diff,
+        })
+    }
+
+    pub fn is_noop(&self) -> bool {
+        self.diff.is_empty()
+    }
+}
+
+pub fn output_block(output: &OutputSnapshot) -> String {
+    let mut block = format!("output \"{}\" {{\n", escape(&output.name));
+    for (_, node) in managed_nodes(output) {
+        block.push_str(&format!("    {node}\n"));
+    }
+    block.push_str("}\n");
+    block
+}
+
+/// Child nodes of an output block that niri-cast writes, in the order a new
+/// block lists them. Any other child belongs to the user.
+const MANAGED_NODES: [&str; 5] = ["off", "mode", "scale", "transform", "position"];
+
+fn managed_nodes(output: &OutputSnapshot) -> Vec<(&'static str, String)> {
+    let mut nodes = Vec::new();
+    if !output.enabled {
+        nodes.push(("off", "off".to_string()));
+    }
+    if let Some(mode) = &output.mode {
+        nodes.push((
+            "mode",
+            format!(
+                "mode \"{}x{}@{}.{:03}\"",
+                mode.width,
+                mode.height,
+                mode.refresh_rate / 1000,
+                mode.refresh_rate % 1000
+            ),
+        ));
+    }
+    if let Some(scale) = output.scale {
+        nodes.push(("scale", format!("scale {scale}")));
+    }
+    if let Some(transform) = output.transform {
+        nodes.push(("transform", format!("transform \"{}\"", transform.as_str())));
+    }
+    if let Some((x, y)) = output.position {
+        nodes.push(("position", format!("position x={x} y={y}")));
+    }
+    nodes
+}
+
+/// Rewrites the `mode`, `scale`, `transform`, `position` and `off` children
+/// of every top-level `output "<name>" { ... }` block of an exported output
+/// (niri matches output names case-insensitively) and appends blocks for
+/// outputs the file does not mention yet. Other children, comments and the
+/// rest of the file are left byte for byte as they were.
+pub fn update_output_blocks(config: &str, outputs: &[OutputSnapshot]) -> String {
+    let existing = output_blocks(config);
+    let mut replacements = Vec::new();
+    let mut appended = Vec::new();
+    for output in outputs {
+        let mut found = false;
+        for (_, range) in existing
+            .iter()
+            .filter(|(name, _)| name.eq_ignore_ascii_case(&output.name))
+        {
+            replacements.push((range.clone(), rewrite_block(&config[range.clone()], output)));
+            found = true;
+        }
+        if !found {
+            appended.push(output_block(output));
+        }
+    }
+
+    let mut updated = config.to_string();
+    replacements.sort_by_key(|(range, _)| std::cmp::Reverse(range.start));
+    for (range, block) in replacements {
+        updated.replace_range(range, &block);
+    }
+    for block in appended {
+        if !updated.is_empty() && !updated.ends_with("\n\n") {
+            if !updated.ends_with('\n') {
+                updated.push('\n');
+            }
+            updated.push('\n');
+        }
+        updated.push_str(&block);
+    }
+    updated
+}
+
+/// Updates the managed children inside one existing output block: the first
+/// of each is rewritten in place, repeats and children the output no longer
+/// needs (e.g. `off` once it is on) are dropped, missing ones are added at
+/// the end of the block.
+fn rewrite_block(block: &str, output: &OutputSnapshot) -> String {
+    let (Some(open), Some(close)) = (block_open_brace(block), block.rfind('}')) else {
+        return output_block(output);
+    };
+    let body = &block[open + 1..close];
+    let mut wanted = managed_nodes(output);
+    let mut edits = Vec::new();
+    for (name, range) in child_nodes(body) {
+        if !MANAGED_NODES.contains(&name.as_str()) {
+            continue;
+        }
+        match wanted.iter().position(|(wanted, _)| *wanted == name) {
+            Some(idx) => edits.push((range, wanted.remove(idx).1)),
+            None => edits.push((removal_range(body, range), String::new())),
+        }
+    }
+
+    let mut body = body.to_string();
+    for (range, text) in edits.into_iter().rev() {
+        body.replace_range(range, &text);
+    }
+    if !wanted.is_empty() {
+        match body.rfind('\n') {
+            Some(last_line) if body[last_line..].trim().is_empty() => {
+                let added = wanted
+                    .iter()
+                    .map(|(_, node)| format!("    {node}\n"))
+                    .collect::<String>();
+                body.insert_str(last_line + 1, &added);
+            }
+            _ => {
+                let end = body.trim_end().len();
+                if end == 0 {
+                    body = " ".to_string();
+                }
+                let separator = match body[..end].chars().last() {
+                    None | Some(';') | Some('{') => "",
+                    Some(_) => ";",
+                };
+                let added = wanted
+                    .iter()
+                    .map(|(_, node)| format!(" {node};"))
+                    .collect::<String>();
+                body.insert_str(end, &format!("{separator}{added}"));
+            }
+        }
+    }
+    format!("{}{body}{}", &block[..=open], &block[close..])
+}
+
+fn block_open_brace(block: &str) -> Option<usize> {
+    let bytes = block.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'"' => {
+                i = skip_string(bytes, i);
+                continue;
+            }
+            b'{' => return Some(i),
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+/// Top-level child nodes of a block body with the byte range of each node,
+/// without its `;` terminator or trailing comments.
+fn child_nodes(body: &str) -> Vec<(String, Range<usize>)> {
+    let bytes = body.as_bytes();
+    let mut nodes = Vec::new();
+    let mut start: Option<usize> = None;
+    let mut end = 0;
+    let mut depth = 0usize;
+    let mut i = 0;
+    let mut finish = |start: &mut Option<usize>, end: usize| {
+        if let Some(start) = start.take() {
+            let name = body[start..end]
+                .split(|c: char| c.is_whitespace() || c == '{' || c == '"')
+                .next()
+                .unwrap_or_default()
+                .to_string();
+            nodes.push((name, start..end));
+        }
+    };
+
+    while i < bytes.len() {
+        let rest = &bytes[i..];
+        match bytes[i] {
+            b'"' => {
+                start.get_or_insert(i);
+                i = skip_string(bytes, i);
+                end = i;
+                continue;
+            }
+            b'/' if rest.starts_with(b"//") => {
+                i += rest.iter().position(|b| *b == b'\n').unwrap_or(rest.len());
+                continue;
+            }
+            b'/' if rest.starts_with(b"/*") => {
+                i += rest
+                    .windows(2)
+                    .position(|w| w == b"*/")
+                    .map_or(rest.len(), |p| p + 2);
+                continue;
+            }
+            b'{' => {
+                start.get_or_insert(i);
+                depth += 1;
+                end = i + 1;
+            }
+            b'}' => {
+                depth = depth.saturating_sub(1);
+                end = i + 1;
+                if depth == 0 {
+                    finish(&mut start, end);
+                }
+            }
+            b'\n' | b';' if depth == 0 => finish(&mut start, end),
+            b if b.is_ascii_whitespace() => {}
+            _ => {
+                start.get_or_insert(i);
+                end = i + 1;
+            }
+        }
+        i += 1;
+    }
+    finish(&mut start, end);
+    nodes
+}
+
+/// A dropped node takes its `;` with it, and its whole line when nothing
+/// else is on it.
+fn removal_range(body: &str, node: Range<usize>) -> Range<usize> {
+    let mut end = node.end;
+    if body[end..].starts_with(';') {
+        end += 1;
+    }
+    let line_start = body[..node.start].rfind('\n').map_or(0, |p| p + 1);
+    let line_end = body[end..].find('\n').map(|p| end + p + 1);
+    match line_end {
+        Some(line_end)
+            if body[line_start..node.start].trim().is_empty()
+                && body[end..line_end].trim().is_empty() =>
+        {
+            line_start..line_end
+        }
+        _ => node.start..end,
+    }
+}
+
+/// Byte ranges of the top-level output nodes, from the start of their line
+/// through the closing brace and its newline.
+fn output_blocks(config: &str) -> Vec<(String, Range<usize>)> {
+    let bytes = config.as_bytes();
+    let mut blocks = Vec::new();
+    let mut open: Option<(String, usize)> = None;
+    let mut depth = 0usize;
+    let mut at_node_start = true;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let rest = &bytes[i..];
+        match bytes[i] {
+            b'"' => {
+                i = skip_string(bytes, i);
+                at_node_start = false;
+                continue;
+            }
+            b'/' if rest.starts_with(b"//") => {
+                i += rest.iter().position(|b| *b == b'\n').unwrap_or(rest.len());
+                continue;
+            }
+            b'/' if rest.starts_with(b"/*") => {
+                i += rest
+                    .windows(2)
+                    .position(|w| w == b"*/")
+                    .map_or(rest.len(), |p| p + 2);
+                continue;
+            }
+            b'{' => {
+                depth += 1;
+                at_node_start = true;
+            }
+            b'}' => {
+                depth = depth.saturating_sub(1);
+                at_node_start = true;
+                if depth == 0 {
+                    if let Some((name, start)) = open.take() {
+                        let mut end = i + 1;
+                        if bytes[end..].starts_with(b"\r\n") {
+                            end += 2;
+                        } else if bytes[end..].starts_with(b"\n") {
+                            end += 1;
+                        }
+                        blocks.push((name, start..end));
+                    }
+                }
+            }
+            b'\n' | b';' => at_node_start = true,
+            b if b.is_ascii_whitespace() => {}
+            _ => {
+                if at_node_start && depth == 0 && open.is_none() {
+                    if let Some(name) = output_node_name(rest) {
+                        let line_start = bytes[..i]
+                            .iter()
+                            .rposition(|b| *b == b'\n')
+                            .map_or(0, |p| p + 1);
+                        open = Some((name, line_start));
+                    }
+                }
+                at_node_start = false;
+            }
+        }
+        i += 1;
+    }
+    blocks
+}
+
+fn output_node_name(rest: &[u8]) -> Option<String> {
+    let after = rest.strip_prefix(b"output")?;
+    let name_start = after.iter().position(|b| !b.is_ascii_whitespace())?;
+    if name_start == 0 || after[name_start] != b'"' {
+        return None;
+    }
+    let quoted = &after[name_start..];
+    let end = skip_string(quoted, 0);
+    let inner = quoted.get(1..end.checked_sub(1)?)?;
+    String::from_utf8(inner.to_vec()).ok()
+}
+
+fn skip_string(bytes: &[u8], start: usize) -> usize {
+    let mut i = start + 1;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            b'"' => return i + 1,
+            _ => i += 1,
+        }
+    }
+    bytes.len()
+}
+
+fn escape(name: &str) -> String {
+    name.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Line diff between two texts, as `-`/`+` prefixed lines in file order.
+pub fn diff_lines(old: &str, new: &str) -> Vec<String> {
+    let old = old.lines().collect::<Vec<_>>();
+    let new = new.lines().collect::<Vec<_>>();
+
+    // Longest common subsequence table, filled from the end.
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut diff = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            diff.push(format!("- {}", old[i]));
+            i += 1;
+        } else {
+            diff.push(format!("+ {}", new[j]));
+            j += 1;
+        }
+    }
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{diff_lines, output_block, update_output_blocks};
+    use crate::adapters::niri::{NiriMode, NiriTransform};
+    use crate::core::snapshot::OutputSnapshot;
+
+    fn tv() -> OutputSnapshot {
+        OutputSnapshot {
+            name: "HDMI-A-1".to_string(),
+            enabled: true,
+            position: Some((1920, 0)),
+            mode: Some(NiriMode {
+                width: 1920,
+                height: 1080,
+                refresh_rate: 59940,
+                is_preferred: true,
+            }),
+            scale: Some(1.5),
+            transform: Some(NiriTransform::Normal),
+        }
+    }
+
+    #[test]
+    fn renders_output_block() {
+        assert_eq!(
+            output_block(&tv()),
+            "output \"HDMI-A-1\" {\n    mode \"1920x1080@59.940\"\n    scale 1.5\n    transform \"normal\"\n    position x=1920 y=0\n}\n"
+        );
+    }
+
+    #[test]
+    fn updates_existing_block_and_keeps_the_rest() {
+        let config = "\
+input {
+    keyboard { xkb { layout \"us\"; } }
+}
+
+// output \"HDMI-A-1\" { scale 3; }
+output \"hdmi-a-1\" {
+    off
+    scale 2
+    /* keep { braces } in comments */
+    variable-refresh-rate // for games
+    position x=0 y=0
+    scale 3
+}
+
+output \"eDP-1\" { scale 1.25; }
+";
+        let updated = update_output_blocks(config, &[tv()]);
+        assert_eq!(
+            updated,
+            "\
+input {
+    keyboard { xkb { layout \"us\"; } }
+}
+
+// output \"HDMI-A-1\" { scale 3; }
+output \"hdmi-a-1\" {
+    scale 1.5
+    /* keep { braces } in comments */
+    variable-refresh-rate // for games
+    position x=1920 y=0
+    mode \"1920x1080@59.940\"
+    transform \"normal\"
+}
+
+output \"eDP-1\" { scale 1.25; }
+"
+        );
+        assert_eq!(update_output_blocks(&updated, &[tv()]), updated);
+    }
+
+    #[test]
+    fn updates_every_matching_single_line_block() {
+        let mut tv = tv();
+        tv.name = "eDP-1".to_string();
+        tv.mode = None;
+        tv.transform = None;
+        let config = "output \"eDP-1\" { scale 1.25; focus-at-startup; }\noutput \"EDP-1\" { off }\n";
+        assert_eq!(
+            update_output_blocks(config, &[tv]),
+            "output \"eDP-1\" { scale 1.5; focus-at-startup; position x=1920 y=0; }\noutput \"EDP-1\" { scale 1.5; position x=1920 y=0; }\n"
+        );
+    }
+
+    #[test]
+    fn appends_missing_blocks() {
+        let updated = update_output_blocks("layout { gaps 8; }", &[tv()]);
+        assert!(updated.starts_with("layout { gaps 8; }\n\noutput \"HDMI-A-1\" {\n"));
+        assert_eq!(update_output_blocks(&updated, &[tv()]), updated);
+    }
+
+    #[test]
+    fn diffs_changed_lines() {
+        assert_eq!(
+            diff_lines("a\nb\nc\n", "a\nx\nc\nd\n"),
+            vec!["- b", "+ x", "+ d"]
+        );
+        assert!(diff_lines("same\n", "same\n").is_empty());
+    }
+}
@@ -1,12 +1,324 @@
-use crate::adapters::{audio::AudioAdapter, niri::NiriAdapter};
+use crate::adapters::{
+    audio::{AudioAdapter, AudioSink},
+    niri::NiriWorkspace,
+    system::SystemAdapter,
+};
+use std::fmt;
 
-pub fn switch_to_tv_mode(niri: &NiriAdapter, audio: &AudioAdapter) -> anyhow::Result<String> {
-    let outputs = niri.list_hdmi_outputs()?;
-    let sink = audio.select_first_hdmi_sink()?;
+use crate::config::AudioRule;
+use crate::core::audio::{
+    card_on_gpu, choose_sink_for_gpu, hdmi_sink_on_gpu, switch_card_profile, ProfileTarget,
+};
+use crate::core::layout::{LayoutCastMode, Side};
+use crate::core::routing::switch_default_sink;
+use crate::core::transaction::LayoutTransaction;
 
-    Ok(format!(
-        "tv mode updated: hdmi_outputs={}, selected_sink={}",
-        outputs.len(),
-        sink.unwrap_or_else(|| "none".to_string())
+/// What niri-cast is currently doing with the displays.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum CastState {
+    #[default]
+    Idle,
+    Extended {
+        side: Side,
+        target: String,
+    },
+    Mirrored {
+        source: String,
+        target: String,
+    },
+    HdmiOnly {
+        target: String,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CastTransitionError {
+    NotCasting,
+    MirrorOntoItself(String),
+    MissingSource(&'static str),
+}
+
+impl fmt::Display for CastTransitionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CastTransitionError::NotCasting => write!(f, "not casting; nothing to stop"),
+            CastTransitionError::MirrorOntoItself(output) => {
+                write!(f, "cannot mirror {output} onto itself")
+            }
+            CastTransitionError::MissingSource(mode) => {
+                write!(f, "{mode} needs a second output to pair with the target")
+            }
+        }
+    }
+}
+
+impl std::error::Error for CastTransitionError {}
+
+impl CastState {
+    /// The state a cast mode leads to, given the target and the output it
+    /// is paired with.
+    pub fn for_mode(
+        mode: LayoutCastMode,
+        target: &str,
+        primary: Option<&str>,
+    ) -> Result<Self, CastTransitionError> {
+        let target = target.to_string();
+        if let Some(side) = mode.extend_side() {
+            return Ok(CastState::Extended { side, target });
+        }
+        match mode {
+            LayoutCastMode::Mirror => {
+                let source = primary.ok_or(CastTransitionError::MissingSource("mirror"))?;
+                if source == target {
+                    return Err(CastTransitionError::MirrorOntoItself(target));
+                }
+                Ok(CastState::Mirrored {
+                    source: source.to_string(),
+                    target,
+                })
+            }
+            _ => Ok(CastState::HdmiOnly { target }),
+        }
+    }
+
+    /// Checks that moving from `self` to `next` makes sense. Any casting
+    /// state may follow any other (switching modes, or re-applying one);
+    /// stopping requires that something is being cast.
+    pub fn transition(&self, next: CastState) -> Result<CastState, CastTransitionError> {
+        match (self, &next) {
+            (CastState::Idle, CastState::Idle) => Err(CastTransitionError::NotCasting),
+            (_, CastState::Mirrored { source, target }) if source == target => {
+                Err(CastTransitionError::MirrorOntoItself(target.clone()))
+            }
+            _ => Ok(next),
+        }
+    }
+
+    pub fn is_casting(&self) -> bool {
+        !matches!(self, CastState::Idle)
+    }
+
+    pub fn target(&self) -> Option<&str> {
+        match self {
+            CastState::Idle => None,
+            CastState::Extended { target, .. }
+            | CastState::Mirrored { target, .. }
+            | CastState::HdmiOnly { target } => Some(target),
+        }
+    }
+
+    pub fn layout_mode(&self) -> Option<LayoutCastMode> {
+        match self {
+            CastState::Idle => None,
+            CastState::Extended { side, .. } => Some(match side {
+                Side::Right => LayoutCastMode::ExtendRight,
+                Side::Left => LayoutCastMode::ExtendLeft,
+                Side::Above => LayoutCastMode::ExtendAbove,
+                Side::Below => LayoutCastMode::ExtendBelow,
+            }),
+            CastState::Mirrored { .. } => Some(LayoutCastMode::Mirror),
+            CastState::HdmiOnly { .. } => Some(LayoutCastMode::HdmiOnly),
+        }
+    }
+}
+
+impl fmt::Display for CastState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CastState::Idle => write!(f, "idle"),
+            CastState::Extended { side, target } => {
+                write!(f, "extended ({target} {})", side.relation())
+            }
+            CastState::Mirrored { source, target } => {
+                write!(f, "mirroring {source} onto {target}")
+            }
+            CastState::HdmiOnly { target } => write!(f, "{target} only"),
+        }
+    }
+}
+
+/// Audio moved onto a cast output, remembering what was playing before so
+/// leaving cast mode can put it back.
+#[derive(Debug, Clone)]
+pub struct TvAudio {
+    pub output: String,
+    pub sink: AudioSink,
+    pub previous: Option<AudioSink>,
+}
+
+/// Makes the HDMI sink belonging to `output`'s GPU the default and routes
+/// the playing streams by `rules`. A card is flipped to its HDMI profile
+/// first when no HDMI sink is exposed yet, or when the output's GPU has a
+/// card but no HDMI sink of its own (its audio profile is off).
+pub fn switch_to_tv_mode(
+    audio: &AudioAdapter,
+    system: &SystemAdapter,
+    output: &str,
+    rules: &[AudioRule],
+) -> anyhow::Result<(TvAudio, usize)> {
+    let gpu = system.drm_connector_pci_address(output);
+    let devices = audio.list_devices()?;
+    let mut sinks = devices.sinks;
+    let previous = sinks.iter().find(|sink| sink.is_default).cloned();
+    let cards = if !sinks.iter().any(|sink| sink.is_hdmi) {
+        devices.cards
+    } else if let Some(gpu) = gpu
+        .as_deref()
+        .filter(|gpu| hdmi_sink_on_gpu(&sinks, gpu).is_none())
+    {
+        devices
+            .cards
+            .into_iter()
+            .filter(|card| card_on_gpu(card, gpu))
+            .collect()
+    } else {
+        Vec::new()
+    };
+    if switch_card_profile(audio, &cards, ProfileTarget::Tv, gpu.as_deref())?.is_some() {
+        sinks = audio.list_sink_objects()?;
+    }
+
+    let sink = choose_sink_for_gpu(&sinks, gpu.as_deref())
+        .cloned()
+        .ok_or_else(|| anyhow::anyhow!("no HDMI sink available for {output}"))?;
+    let moved = switch_default_sink(audio, &sink.id, rules, Some(&sink.node_name))?;
+
+    let previous = previous.filter(|previous| previous.node_name != sink.node_name);
+    Ok((
+        TvAudio {
+            output: output.to_string(),
+            sink,
+            previous,
+        },
+        moved,
     ))
 }
+
+/// Puts audio back on the sink that was default before casting. Sinks are
+/// matched by `node.name`: PipeWire recreates nodes with new ids when a
+/// card profile changes. If that sink vanished with a profile switch, the
+/// laptop profile is brought back and its first non-HDMI sink used instead.
+pub fn leave_tv_mode(
+    audio: &AudioAdapter,
+    tv_audio: &TvAudio,
+) -> anyhow::Result<Option<(AudioSink, usize)>> {
+    let Some(previous) = &tv_audio.previous else {
+        return Ok(None);
+    };
+    let devices = audio.list_devices()?;
+    let mut sinks = devices.sinks;
+    let is_previous = |sink: &&AudioSink| sink.node_name == previous.node_name;
+    if !sinks.iter().any(|sink| is_previous(&sink))
+        && switch_card_profile(audio, &devices.cards, ProfileTarget::Laptop, None)?.is_some()
+    {
+        sinks = audio.list_sink_objects()?;
+    }
+    let sink = sinks
+        .iter()
+        .find(is_previous)
+        .or_else(|| sinks.iter().find(|sink| !sink.is_hdmi))
+        .cloned()
+        .ok_or_else(|| anyhow::anyhow!("{} is gone and no laptop sink is left", previous.name))?;
+    let moved = switch_default_sink(audio, &sink.id, &[], None)?;
+    Ok(Some((sink, moved)))
+}
+
+/// Workspaces worth keeping when everything but `target` goes dark: the
+/// focused one and those with windows or a name. niri's trailing empty
+/// workspaces are left alone.
+pub fn workspaces_to_move<'a>(
+    workspaces: &'a [NiriWorkspace],
+    target: &str,
+) -> Vec<&'a NiriWorkspace> {
+    workspaces
+        .iter()
+        .filter(|w| w.output.as_deref() != Some(target))
+        .filter(|w| w.is_focused || w.active_window_id.is_some() || w.name.is_some())
+        .collect()
+}
+
+/// Moves the chosen workspaces onto `target` and returns their labels.
+pub fn move_workspaces_to(
+    tx: &mut LayoutTransaction,
+    workspaces: &[NiriWorkspace],
+    target: &str,
+) -> anyhow::Result<Vec<String>> {
+    let mut moved = Vec::new();
+    for workspace in workspaces_to_move(workspaces, target) {
+        tx.move_workspace(workspace, target)?;
+        moved.push(workspace.label());
+    }
+    Ok(moved)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{workspaces_to_move, CastState, CastTransitionError};
+    use crate::adapters::niri::NiriWorkspace;
+    use crate::core::layout::{LayoutCastMode, Side};
+
+    fn workspace(id: u64, output: &str, name: Option<&str>, window: Option<u64>) -> NiriWorkspace {
+        NiriWorkspace {
+            id,
+            idx: id as u8,
+            name: name.map(str::to_string),
+            output: Some(output.to_string()),
+            is_focused: false,
+            active_window_id: window,
+        }
+    }
+
+    #[test]
+    fn picks_occupied_or_named_workspaces_off_the_target() {
+        let workspaces = vec![
+            workspace(1, "eDP-1", None, Some(10)),
+            workspace(2, "eDP-1", Some("chat"), None),
+            workspace(3, "eDP-1", None, None),
+            workspace(4, "HDMI-A-1", None, Some(11)),
+        ];
+        let ids = workspaces_to_move(&workspaces, "HDMI-A-1")
+            .iter()
+            .map(|w| w.id)
+            .collect::<Vec<_>>();
+        assert_eq!(ids, vec![1, 2]);
+    }
+
+    #[test]
+    fn builds_states_for_modes() {
+        assert_eq!(
+            CastState::for_mode(LayoutCastMode::ExtendAbove, "HDMI-A-1", Some("eDP-1")),
+            Ok(CastState::Extended {
+                side: Side::Above,
+                target: "HDMI-A-1".to_string()
+            })
+        );
+        assert_eq!(
+            CastState::for_mode(LayoutCastMode::Mirror, "HDMI-A-1", None),
+            Err(CastTransitionError::MissingSource("mirror"))
+        );
+        assert_eq!(
+            CastState::for_mode(LayoutCastMode::Mirror, "HDMI-A-1", Some("HDMI-A-1")),
+            Err(CastTransitionError::MirrorOntoItself("HDMI-A-1".to_string()))
+        );
+        let hdmi_only = CastState::for_mode(LayoutCastMode::HdmiOnly, "DP-1", None).unwrap();
+        assert_eq!(hdmi_only.layout_mode(), Some(LayoutCastMode::HdmiOnly));
+    }
+
+    #[test]
+    fn validates_transitions() {
+        let mirrored = CastState::Mirrored {
+            source: "eDP-1".to_string(),
+            target: "HDMI-A-1".to_string(),
+        };
+        assert_eq!(
+            CastState::Idle.transition(CastState::Idle),
+            Err(CastTransitionError::NotCasting)
+        );
+        assert_eq!(
+            CastState::Idle.transition(mirrored.clone()),
+            Ok(mirrored.clone())
+        );
+        assert_eq!(mirrored.transition(CastState::Idle), Ok(CastState::Idle));
+        assert_eq!(mirrored.target(), Some("HDMI-A-1"));
+    }
+}
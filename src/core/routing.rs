@@ -0,0 +1,139 @@
+use crate::adapters::audio::{AudioAdapter, AudioSink, AudioStream};
+use crate::config::{AudioRule, RuleSink};
+
+/// The sinks a rule can point at, resolved against the current sink list.
+#[derive(Debug, Clone, Copy)]
+pub struct RouteTargets<'a> {
+    pub default: &'a AudioSink,
+    pub tv: Option<&'a AudioSink>,
+    pub laptop: Option<&'a AudioSink>,
+}
+
+impl<'a> RouteTargets<'a> {
+    /// The TV is the cast output's HDMI sink, given by its `node.name`
+    /// since ids change with card profiles; the laptop is the first
+    /// non-HDMI sink. `tv` rules fall back to the default without a cast
+    /// sink rather than guess a connector.
+    pub fn from_sinks(sinks: &'a [AudioSink], tv_node: Option<&str>) -> Option<Self> {
+        let default = sinks.iter().find(|sink| sink.is_default)?;
+        Some(Self {
+            default,
+            tv: tv_node.and_then(|node| sinks.iter().find(|sink| sink.node_name == node)),
+            laptop: sinks.iter().find(|sink| !sink.is_hdmi),
+        })
+    }
+
+    pub fn destination(&self, rules: &[AudioRule], stream: &AudioStream) -> &'a AudioSink {
+        match rules.iter().find(|rule| rule_matches(rule, stream)) {
+            Some(rule) => match rule.sink {
+                RuleSink::Tv => self.tv.unwrap_or(self.default),
+                RuleSink::Laptop => self.laptop.unwrap_or(self.default),
+            },
+            None => self.default,
+        }
+    }
+}
+
+pub fn rule_matches(rule: &AudioRule, stream: &AudioStream) -> bool {
+    std::iter::once(&stream.application)
+        .chain(stream.binary.as_ref())
+        .any(|name| name.eq_ignore_ascii_case(&rule.application))
+}
+
+/// Streams that are not on their destination yet, paired with it.
+pub fn plan_moves<'a, 's>(
+    rules: &[AudioRule],
+    streams: &'s [AudioStream],
+    targets: &RouteTargets<'a>,
+) -> Vec<(&'s AudioStream, &'a AudioSink)> {
+    streams
+        .iter()
+        .map(|stream| (stream, targets.destination(rules, stream)))
+        .filter(|(stream, sink)| stream.sink_id.as_deref() != Some(sink.id.as_str()))
+        .collect()
+}
+
+/// Makes a sink the default, then moves streams according to `rules`;
+/// streams without a rule follow the new default. `tv_node` is the cast
+/// output's sink for `tv` rules. Pass no rules to move everything, e.g. when
+/// casting stops.
+pub fn switch_default_sink(
+    audio: &AudioAdapter,
+    sink_id: &str,
+    rules: &[AudioRule],
+    tv_node: Option<&str>,
+) -> anyhow::Result<usize> {
+    audio.set_default_by_id(sink_id)?;
+    let mut devices = audio.list_devices()?;
+    for sink in &mut devices.sinks {
+        sink.is_default = sink.id == sink_id;
+    }
+    let Some(targets) = RouteTargets::from_sinks(&devices.sinks, tv_node) else {
+        return Ok(0);
+    };
+    let moved = plan_moves(rules, &devices.streams, &targets)
+        .into_iter()
+        .filter(|(stream, sink)| audio.move_stream(stream, sink).is_ok())
+        .count();
+    Ok(moved)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{plan_moves, RouteTargets};
+    use crate::adapters::audio::fixtures::sink;
+    use crate::adapters::audio::AudioStream;
+    use crate::config::{AudioRule, RuleSink};
+
+    fn stream(application: &str, binary: Option<&str>, sink_id: &str) -> AudioStream {
+        AudioStream {
+            serial: 0,
+            application: application.to_string(),
+            binary: binary.map(str::to_string),
+            title: None,
+            sink_id: Some(sink_id.to_string()),
+        }
+    }
+
+    #[test]
+    fn routes_by_rule_and_sends_the_rest_to_the_default() {
+        let sinks = vec![
+            sink("48", "node-48", true, false),
+            sink("55", "node-55", false, true),
+            sink("61", "node-61", false, true),
+        ];
+        // The cast output's sink, not the first HDMI sink in the list.
+        let targets = RouteTargets::from_sinks(&sinks, Some("node-61")).unwrap();
+        let rules = vec![
+            AudioRule {
+                application: "MPV".to_string(),
+                sink: RuleSink::Tv,
+            },
+            AudioRule {
+                application: "discord".to_string(),
+                sink: RuleSink::Laptop,
+            },
+        ];
+        let streams = vec![
+            stream("mpv", None, "55"),
+            stream("WEBRTC VoiceEngine", Some("Discord"), "55"),
+            stream("Firefox", None, "55"),
+        ];
+
+        let moves = plan_moves(&rules, &streams, &targets)
+            .into_iter()
+            .map(|(stream, sink)| (stream.application.as_str(), sink.id.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            moves,
+            vec![("mpv", "61"), ("WEBRTC VoiceEngine", "48"), ("Firefox", "48")]
+        );
+
+        // Without a cast sink, tv rules stay on the default.
+        let targets = RouteTargets::from_sinks(&sinks, None).unwrap();
+        assert_eq!(targets.destination(&rules, &streams[0]).id, "48");
+
+        // Without rules everything follows the default.
+        assert_eq!(plan_moves(&[], &streams, &targets).len(), 3);
+    }
+}
@@ -0,0 +1,282 @@
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+
+use crate::adapters::niri::{NiriAdapter, NiriMode, NiriOutput, NiriTransform, NiriWorkspace};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OutputSnapshot {
+    pub name: String,
+    pub enabled: bool,
+    pub position: Option<(i32, i32)>,
+    pub mode: Option<NiriMode>,
+    pub scale: Option<f64>,
+    pub transform: Option<NiriTransform>,
+}
+
+impl OutputSnapshot {
+    pub fn capture(output: &NiriOutput) -> Self {
+        let logical = output.logical.as_ref();
+        Self {
+            name: output.name.clone(),
+            enabled: logical.is_some(),
+            position: logical.map(|l| (l.x, l.y)),
+            mode: output.current_mode().cloned(),
+            scale: logical.map(|l| l.scale),
+            transform: logical.map(|l| l.transform),
+        }
+    }
+}
+
+/// Which monitor a workspace lived on. niri keeps workspace ids stable when
+/// a workspace moves, so the id is enough to find it again.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorkspacePlacement {
+    pub id: u64,
+    pub name: Option<String>,
+    pub output: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LayoutSnapshot {
+    pub taken_at: String,
+    pub outputs: Vec<OutputSnapshot>,
+    #[serde(default)]
+    pub workspaces: Vec<WorkspacePlacement>,
+}
+
+impl LayoutSnapshot {
+    pub fn capture(outputs: &[NiriOutput]) -> Self {
+        let now = OffsetDateTime::now_local().unwrap_or_else(|_| OffsetDateTime::now_utc());
+        Self {
+            taken_at: now.format(&Rfc3339).unwrap_or_default(),
+            outputs: outputs.iter().map(OutputSnapshot::capture).collect(),
+            workspaces: Vec::new(),
+        }
+    }
+
+    pub fn with_workspaces(mut self, workspaces: &[NiriWorkspace]) -> Self {
+        self.workspaces = workspaces
+            .iter()
+            .filter_map(|workspace| {
+                Some(WorkspacePlacement {
+                    id: workspace.id,
+                    name: workspace.name.clone(),
+                    output: workspace.output.clone()?,
+                })
+            })
+            .collect();
+        self
+    }
+}
+
+/// Puts every output back exactly as captured. Enabled outputs are
+/// configured first so the session never ends up without a screen; outputs
+/// that were off are turned off last, and workspaces return to their
+/// monitors after that. Returns the outputs that were skipped because they
+/// are no longer connected.
+pub fn restore(niri: &NiriAdapter, snapshot: &LayoutSnapshot) -> anyhow::Result<Vec<String>> {
+    let connected = niri.connected_output_names()?;
+    let (present, missing): (Vec<_>, Vec<_>) = snapshot
+        .outputs
+        .iter()
+        .partition(|output| connected.contains(&output.name));
+
+    for output in present.iter().filter(|output| output.enabled) {
+        niri.output_on(&output.name)?;
+        if let Some(mode) = &output.mode {
+            niri.set_mode(&output.name, mode)?;
+        }
+        if let Some(scale) = output.scale {
+            niri.set_scale(&output.name, scale)?;
+        }
+        if let Some(transform) = output.transform {
+            niri.set_transform(&output.name, transform)?;
+        }
+        match output.position {
+            Some((x, y)) => niri.set_position(&output.name, x, y)?,
+            None => niri.set_position_auto(&output.name)?,
+        }
+    }
+
+    for output in present.iter().filter(|output| !output.enabled) {
+        niri.output_off(&output.name)?;
+    }
+
+    if !snapshot.workspaces.is_empty() {
+        let enabled = present
+            .iter()
+            .filter(|output| output.enabled)
+            .map(|output| output.name.as_str())
+            .collect::<Vec<_>>();
+        for workspace in niri.workspaces()? {
+            let Some(placement) = snapshot.workspaces.iter().find(|p| p.id == workspace.id)
+            else {
+                continue;
+            };
+            if workspace.output.as_deref() != Some(placement.output.as_str())
+                && enabled.contains(&placement.output.as_str())
+            {
+                niri.move_workspace_to_monitor(workspace.id, &placement.output)?;
+            }
+        }
+    }
+
+    Ok(missing.into_iter().map(|output| output.name.clone()).collect())
+}
+
+#[derive(Debug, Clone)]
+pub struct SnapshotStore {
+    path: PathBuf,
+}
+
+impl SnapshotStore {
+    pub fn new() -> anyhow::Result<Self> {
+        let mut dir = dirs::config_dir().context("could not resolve XDG config dir")?;
+        dir.push("niri-cast");
+        fs::create_dir_all(&dir).context("failed to create config directory")?;
+
+        let mut path = dir;
+        path.push("layout-snapshot.json");
+        Ok(Self { path })
+    }
+
+    pub fn load(&self) -> anyhow::Result<Option<LayoutSnapshot>> {
+        if !self.path.exists() {
+            return Ok(None);
+        }
+        let content = fs::read_to_string(&self.path).context("failed to read layout snapshot")?;
+        let parsed =
+            serde_json::from_str(&content).context("failed to parse layout-snapshot.json")?;
+        Ok(Some(parsed))
+    }
+
+    pub fn save(&self, snapshot: &LayoutSnapshot) -> anyhow::Result<()> {
+        let content = serde_json::to_string_pretty(snapshot)?;
+        fs::write(&self.path, content).context("failed to write layout snapshot")?;
+        Ok(())
+    }
+
+    pub fn clear(&self) -> anyhow::Result<()> {
+        if self.path.exists() {
+            fs::remove_file(&self.path).context("failed to remove layout snapshot")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{restore, LayoutSnapshot, OutputSnapshot, WorkspacePlacement};
+    use crate::adapters::niri::{NiriAdapter, NiriMode, NiriTransform};
+    use crate::adapters::niri_ipc::stub::StubNiri;
+
+    const OUTPUTS_REPLY: &str = r#"{"Ok":{"Outputs":{"eDP-1":{"name":"eDP-1","logical":null},"HDMI-A-1":{"name":"HDMI-A-1","logical":null}}}}"#;
+    const APPLIED: &str = r#"{"Ok":{"OutputConfigChanged":"Applied"}}"#;
+
+    fn snapshot() -> LayoutSnapshot {
+        LayoutSnapshot {
+            taken_at: "2026-01-01T00:00:00Z".to_string(),
+            outputs: vec![
+                OutputSnapshot {
+                    name: "HDMI-A-1".to_string(),
+                    enabled: false,
+                    position: None,
+                    mode: None,
+                    scale: None,
+                    transform: None,
+                },
+                OutputSnapshot {
+                    name: "eDP-1".to_string(),
+                    enabled: true,
+                    position: Some((0, 0)),
+                    mode: Some(NiriMode {
+                        width: 2880,
+                        height: 1800,
+                        refresh_rate: 90000,
+                        is_preferred: true,
+                    }),
+                    scale: Some(1.5),
+                    transform: Some(NiriTransform::Normal),
+                },
+                OutputSnapshot {
+                    name: "DP-3".to_string(),
+                    enabled: true,
+                    position: Some((1920, 0)),
+                    mode: None,
+                    scale: Some(1.0),
+                    transform: Some(NiriTransform::Normal),
+                },
+            ],
+            workspaces: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn snapshot_round_trips_through_json() {
+        let snapshot = snapshot();
+        let json = serde_json::to_string(&snapshot).unwrap();
+        assert_eq!(serde_json::from_str::<LayoutSnapshot>(&json).unwrap(), snapshot);
+    }
+
+    #[test]
+    fn restores_enabled_outputs_before_turning_others_off() {
+        let mut replies = vec![OUTPUTS_REPLY];
+        replies.extend([APPLIED; 6]);
+        let stub = StubNiri::serve(&replies);
+        let niri = NiriAdapter::with_socket(&stub.path);
+
+        let missing = restore(&niri, &snapshot()).unwrap();
+        assert_eq!(missing, vec!["DP-3".to_string()]);
+
+        let requests = stub.requests();
+        assert_eq!(requests.len(), 7);
+        assert_eq!(requests[1], r#"{"Output":{"output":"eDP-1","action":"On"}}"#);
+        assert!(requests[2].contains(r#""Mode""#));
+        assert!(requests[3].contains(r#""Scale":{"scale":{"Specific":1.5}}"#));
+        assert!(requests[4].contains(r#""Transform":{"transform":"Normal"}"#));
+        assert!(requests[5].contains(r#""Specific":{"x":0,"y":0}"#));
+        assert_eq!(requests[6], r#"{"Output":{"output":"HDMI-A-1","action":"Off"}}"#);
+    }
+
+    #[test]
+    fn moves_workspaces_back_to_enabled_monitors() {
+        let mut snapshot = snapshot();
+        snapshot.workspaces = vec![
+            WorkspacePlacement {
+                id: 1,
+                name: None,
+                output: "eDP-1".to_string(),
+            },
+            WorkspacePlacement {
+                id: 2,
+                name: Some("media".to_string()),
+                output: "HDMI-A-1".to_string(),
+            },
+        ];
+        let workspaces = r#"{"Ok":{"Workspaces":[
+            {"id":1,"idx":1,"output":"HDMI-A-1"},
+            {"id":2,"idx":2,"name":"media","output":"HDMI-A-1"},
+            {"id":3,"idx":1,"output":"eDP-1"}]}}"#
+            .replace('\n', "");
+        let mut replies = vec![OUTPUTS_REPLY];
+        replies.extend([APPLIED; 6]);
+        replies.extend([workspaces.as_str(), r#"{"Ok":"Handled"}"#]);
+        let stub = StubNiri::serve(&replies);
+        let niri = NiriAdapter::with_socket(&stub.path);
+
+        restore(&niri, &snapshot).unwrap();
+
+        let requests = stub.requests();
+        assert_eq!(requests.len(), 9);
+        assert_eq!(requests[7], r#""Workspaces""#);
+        assert_eq!(
+            requests[8],
+            r#"{"Action":{"MoveWorkspaceToMonitor":{"output":"eDP-1","reference":{"Id":1}}}}"#
+        );
+    }
+}
@@ -0,0 +1,227 @@
+use std::collections::BTreeMap;
+
+use crate::adapters::niri::{NiriAdapter, NiriOutput, NiriWorkspace};
+
+/// One change made through a transaction, with what is needed to undo it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Step {
+    TurnedOn(String),
+    TurnedOff {
+        name: String,
+        position: Option<(i32, i32)>,
+    },
+    Moved {
+        name: String,
+        from: (i32, i32),
+    },
+    MovedWorkspace {
+        id: u64,
+        label: String,
+        from: String,
+    },
+}
+
+#[derive(Debug, Clone, Copy)]
+struct OutputState {
+    enabled: bool,
+    position: Option<(i32, i32)>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct RollbackReport {
+    pub undone: Vec<String>,
+    pub failed: Vec<String>,
+}
+
+impl RollbackReport {
+    pub fn summary(&self) -> String {
+        let mut parts = Vec::new();
+        if self.undone.is_empty() && self.failed.is_empty() {
+            parts.push("nothing had changed".to_string());
+        }
+        if !self.undone.is_empty() {
+            parts.push(format!("rolled back: {}", self.undone.join(", ")));
+        }
+        if !self.failed.is_empty() {
+            parts.push(format!("could not roll back: {}", self.failed.join(", ")));
+        }
+        parts.join("; ")
+    }
+}
+
+/// Applies output changes through niri while recording how to undo each
+/// one. Calls that would not change anything are sent but not recorded, so
+/// a rollback only touches what this transaction actually changed.
+#[derive(Debug)]
+pub struct LayoutTransaction {
+    niri: NiriAdapter,
+    state: BTreeMap<String, OutputState>,
+    steps: Vec<Step>,
+}
+
+impl LayoutTransaction {
+    pub fn new(niri: NiriAdapter, outputs: &[NiriOutput]) -> Self {
+        let state = outputs
+            .iter()
+            .map(|output| {
+                let logical = output.logical.as_ref();
+                let state = OutputState {
+                    enabled: logical.is_some(),
+                    position: logical.map(|l| (l.x, l.y)),
+                };
+                (output.name.clone(), state)
+            })
+            .collect();
+        Self {
+            niri,
+            state,
+            steps: Vec::new(),
+        }
+    }
+
+    pub fn output_on(&mut self, name: &str) -> anyhow::Result<()> {
+        self.niri.output_on(name)?;
+        let state = self.state_mut(name);
+        if !state.enabled {
+            state.enabled = true;
+            self.steps.push(Step::TurnedOn(name.to_string()));
+        }
+        Ok(())
+    }
+
+    pub fn output_off(&mut self, name: &str) -> anyhow::Result<()> {
+        self.niri.output_off(name)?;
+        let state = self.state_mut(name);
+        if state.enabled {
+            state.enabled = false;
+            let position = state.position;
+            self.steps.push(Step::TurnedOff {
+                name: name.to_string(),
+                position,
+            });
+        }
+        Ok(())
+    }
+
+    pub fn set_position(&mut self, name: &str, x: i32, y: i32) -> anyhow::Result<()> {
+        self.niri.set_position(name, x, y)?;
+        let state = self.state_mut(name);
+        let previous = state.position.replace((x, y));
+        if let Some(from) = previous.filter(|from| *from != (x, y)) {
+            self.steps.push(Step::Moved {
+                name: name.to_string(),
+                from,
+            });
+        }
+        Ok(())
+    }
+
+    pub fn move_workspace(
+        &mut self,
+        workspace: &NiriWorkspace,
+        output: &str,
+    ) -> anyhow::Result<()> {
+        self.niri.move_workspace_to_monitor(workspace.id, output)?;
+        if let Some(from) = workspace.output.as_deref().filter(|from| *from != output) {
+            self.steps.push(Step::MovedWorkspace {
+                id: workspace.id,
+                label: workspace.label(),
+                from: from.to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Undoes every recorded step, newest first. Undo failures are
+    /// collected instead of stopping the rollback.
+    pub fn rollback(mut self) -> RollbackReport {
+        let mut report = RollbackReport::default();
+        while let Some(step) = self.steps.pop() {
+            let (description, result) = match &step {
+                Step::TurnedOn(name) => (
+                    format!("turned {name} off again"),
+                    self.niri.output_off(name),
+                ),
+                Step::TurnedOff { name, position } => (
+                    format!("turned {name} back on"),
+                    self.niri.output_on(name).and_then(|()| match position {
+                        Some((x, y)) => self.niri.set_position(name, *x, *y),
+                        None => Ok(()),
+                    }),
+                ),
+                Step::Moved { name, from } => (
+                    format!("moved {name} back to {},{}", from.0, from.1),
+                    self.niri.set_position(name, from.0, from.1),
+                ),
+                Step::MovedWorkspace { id, label, from } => (
+                    format!("moved workspace {label} back to {from}"),
+                    self.niri.move_workspace_to_monitor(*id, from),
+                ),
+            };
+            match result {
+                Ok(()) => report.undone.push(description),
+                Err(err) => report.failed.push(format!("{description} ({err})")),
+            }
+        }
+        report
+    }
+
+    fn state_mut(&mut self, name: &str) -> &mut OutputState {
+        self.state.entry(name.to_string()).or_insert(OutputState {
+            enabled: false,
+            position: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LayoutTransaction;
+    use crate::adapters::niri::NiriAdapter;
+    use crate::adapters::niri_ipc::stub::StubNiri;
+
+    const OUTPUTS_REPLY: &str = r#"{"Ok":{"Outputs":{
+        "eDP-1":{"name":"eDP-1","logical":{"x":0,"y":0,"width":1920,"height":1200,"scale":1.0,"transform":"Normal"}},
+        "HDMI-A-1":{"name":"HDMI-A-1","logical":null}}}}"#;
+    const APPLIED: &str = r#"{"Ok":{"OutputConfigChanged":"Applied"}}"#;
+
+    #[test]
+    fn rolls_back_recorded_steps_in_reverse() {
+        let outputs_stub = StubNiri::serve(&[&OUTPUTS_REPLY.replace('\n', "")]);
+        let outputs = NiriAdapter::with_socket(&outputs_stub.path)
+            .outputs_json()
+            .unwrap();
+        outputs_stub.requests();
+
+        let stub = StubNiri::serve(&[
+            APPLIED,
+            APPLIED,
+            APPLIED,
+            r#"{"Err":"no such output"}"#,
+            APPLIED,
+            APPLIED,
+        ]);
+        let mut tx = LayoutTransaction::new(NiriAdapter::with_socket(&stub.path), &outputs);
+        tx.output_on("HDMI-A-1").unwrap();
+        // Already on: sent, but nothing to undo.
+        tx.output_on("eDP-1").unwrap();
+        tx.set_position("eDP-1", 1920, 0).unwrap();
+        assert!(tx.output_off("DP-9").is_err());
+
+        let report = tx.rollback();
+        assert_eq!(
+            report.undone,
+            vec!["moved eDP-1 back to 0,0", "turned HDMI-A-1 off again"]
+        );
+        assert!(report.failed.is_empty());
+
+        let requests = stub.requests();
+        assert_eq!(requests.len(), 6);
+        assert!(requests[4].contains(r#""output":"eDP-1""#));
+        assert!(requests[4].contains(r#""Specific":{"x":0,"y":0}"#));
+        assert_eq!(
+            requests[5],
+            r#"{"Output":{"output":"HDMI-A-1","action":"Off"}}"#
+        );
+    }
+}
@@ -0,0 +1,215 @@
+use std::fmt;
+
+use crate::core::layout::{PlacedOutput, Rect};
+
+/// Outputs closer than this without touching are almost certainly meant to
+/// be adjacent; the pointer cannot cross the sliver between them.
+pub const GAP_TOLERANCE: i32 = 64;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LayoutWarning {
+    Overlap {
+        first: String,
+        second: String,
+    },
+    Gap {
+        first: String,
+        second: String,
+        distance: i32,
+    },
+    Disconnected {
+        groups: Vec<Vec<String>>,
+    },
+}
+
+impl fmt::Display for LayoutWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LayoutWarning::Overlap { first, second } => {
+                write!(f, "{first} and {second} overlap")
+            }
+            LayoutWarning::Gap {
+                first,
+                second,
+                distance,
+            } => write!(
+                f,
+                "{first} and {second} are {distance}px apart; the pointer cannot cross"
+            ),
+            LayoutWarning::Disconnected { groups } => {
+                let groups = groups
+                    .iter()
+                    .map(|group| group.join("+"))
+                    .collect::<Vec<_>>();
+                write!(f, "layout is split into islands: {}", groups.join(" | "))
+            }
+        }
+    }
+}
+
+/// Checks a set of placed outputs for overlaps, near-miss gaps and islands
+/// that share no edge with the rest.
+pub fn validate_layout(outputs: &[PlacedOutput]) -> Vec<LayoutWarning> {
+    let mut warnings = Vec::new();
+
+    for (i, first) in outputs.iter().enumerate() {
+        for second in &outputs[i + 1..] {
+            if overlaps(first.rect, second.rect) {
+                warnings.push(LayoutWarning::Overlap {
+                    first: first.name.clone(),
+                    second: second.name.clone(),
+                });
+            } else if let Some(distance) = gap(first.rect, second.rect) {
+                warnings.push(LayoutWarning::Gap {
+                    first: first.name.clone(),
+                    second: second.name.clone(),
+                    distance,
+                });
+            }
+        }
+    }
+
+    let groups = connected_groups(outputs);
+    if groups.len() > 1 {
+        warnings.push(LayoutWarning::Disconnected { groups });
+    }
+
+    warnings
+}
+
+fn overlaps(a: Rect, b: Rect) -> bool {
+    span_overlap(a.x, a.width, b.x, b.width) > 0 && span_overlap(a.y, a.height, b.y, b.height) > 0
+}
+
+/// Outputs share an edge when they meet along a segment of positive
+/// length; touching only at a corner does not let the pointer through.
+fn touches(a: Rect, b: Rect) -> bool {
+    let meet_x = a.x + a.width == b.x || b.x + b.width == a.x;
+    let meet_y = a.y + a.height == b.y || b.y + b.height == a.y;
+    (meet_x && span_overlap(a.y, a.height, b.y, b.height) > 0)
+        || (meet_y && span_overlap(a.x, a.width, b.x, b.width) > 0)
+}
+
+/// Distance between two facing edges when it is small but not zero.
+fn gap(a: Rect, b: Rect) -> Option<i32> {
+    let dx = (a.x.max(b.x) - (a.x + a.width).min(b.x + b.width)).max(0);
+    let dy = (a.y.max(b.y) - (a.y + a.height).min(b.y + b.height)).max(0);
+    let distance = if dy == 0 && span_overlap(a.y, a.height, b.y, b.height) > 0 {
+        dx
+    } else if dx == 0 && span_overlap(a.x, a.width, b.x, b.width) > 0 {
+        dy
+    } else {
+        return None;
+    };
+    (distance > 0 && distance <= GAP_TOLERANCE).then_some(distance)
+}
+
+fn span_overlap(a_start: i32, a_len: i32, b_start: i32, b_len: i32) -> i32 {
+    (a_start + a_len).min(b_start + b_len) - a_start.max(b_start)
+}
+
+fn connected_groups(outputs: &[PlacedOutput]) -> Vec<Vec<String>> {
+    let mut group_of = vec![usize::MAX; outputs.len()];
+    let mut groups = Vec::new();
+    for start in 0..outputs.len() {
+        if group_of[start] != usize::MAX {
+            continue;
+        }
+        let id = groups.len();
+        let mut members = Vec::new();
+        let mut stack = vec![start];
+        group_of[start] = id;
+        while let Some(current) = stack.pop() {
+            members.push(outputs[current].name.clone());
+            for (next, other) in outputs.iter().enumerate() {
+                let linked = touches(outputs[current].rect, other.rect)
+                    || overlaps(outputs[current].rect, other.rect);
+                if group_of[next] == usize::MAX && linked {
+                    group_of[next] = id;
+                    stack.push(next);
+                }
+            }
+        }
+        members.sort();
+        groups.push(members);
+    }
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{validate_layout, LayoutWarning};
+    use crate::adapters::niri::fixtures::output;
+    use crate::adapters::niri::NiriOutput;
+    use crate::core::layout::current_layout;
+
+    fn validate(outputs: &[NiriOutput]) -> Vec<LayoutWarning> {
+        validate_layout(&current_layout(outputs, None))
+    }
+
+    #[test]
+    fn side_by_side_layout_is_clean() {
+        let outputs = vec![
+            output("eDP-1", Some((0, 0, 1920, 1200))),
+            output("HDMI-A-1", Some((1920, 0, 1920, 1080))),
+        ];
+        assert!(validate(&outputs).is_empty());
+    }
+
+    #[test]
+    fn reports_overlap() {
+        let outputs = vec![
+            output("eDP-1", Some((0, 0, 1920, 1200))),
+            output("HDMI-A-1", Some((1000, 0, 1920, 1080))),
+        ];
+        assert_eq!(
+            validate(&outputs),
+            vec![LayoutWarning::Overlap {
+                first: "eDP-1".to_string(),
+                second: "HDMI-A-1".to_string()
+            }]
+        );
+    }
+
+    #[test]
+    fn reports_small_gap_as_gap_and_island() {
+        let outputs = vec![
+            output("eDP-1", Some((0, 0, 1920, 1200))),
+            output("HDMI-A-1", Some((1930, 0, 1920, 1080))),
+        ];
+        let warnings = validate(&outputs);
+        assert_eq!(
+            warnings[0],
+            LayoutWarning::Gap {
+                first: "eDP-1".to_string(),
+                second: "HDMI-A-1".to_string(),
+                distance: 10
+            }
+        );
+        assert!(matches!(
+            &warnings[1],
+            LayoutWarning::Disconnected { groups } if groups.len() == 2
+        ));
+    }
+
+    #[test]
+    fn corner_contact_does_not_connect() {
+        let outputs = vec![
+            output("eDP-1", Some((0, 0, 1920, 1200))),
+            output("DP-1", Some((1920, 1200, 1920, 1080))),
+        ];
+        assert!(matches!(
+            validate(&outputs).as_slice(),
+            [LayoutWarning::Disconnected { .. }]
+        ));
+    }
+
+    #[test]
+    fn wide_extend_left_is_clean() {
+        let outputs = vec![
+            output("eDP-1", Some((0, 0, 1920, 1200))),
+            output("HDMI-A-1", Some((-3840, 0, 3840, 2160))),
+        ];
+        assert!(validate(&outputs).is_empty());
+    }
+}
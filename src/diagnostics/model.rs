@@ -20,5 +20,6 @@ pub struct DiagnosticItem {
     pub title: String,
     pub severity: Severity,
     pub message: String,
+    #[allow(dead_code)]
     pub remediation: String,
 }
@@ -4,6 +4,7 @@ use crate::diagnostics::{DiagnosticItem, Severity};
 
 #[derive(Debug, Clone)]
 pub struct TroubleshootReport {
+    #[allow(dead_code)]
     pub generated_at: OffsetDateTime,
     pub items: Vec<DiagnosticItem>,
 }
@@ -1,10 +1,14 @@
 mod adapters;
 mod app;
+mod cli;
+mod config;
 mod core;
 mod diagnostics;
 mod profiles;
 mod ui;
 
 fn main() -> anyhow::Result<()> {
-    app::run()
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let command = cli::parse_args(&args)?;
+    cli::run_command(command)
 }
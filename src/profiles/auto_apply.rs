@@ -24,6 +24,7 @@ mod tests {
             name: "tv".to_string(),
             hdmi_output: Some("HDMI-A-1".to_string()),
             audio_sink: None,
+            layout: None,
         }];
 
         let outputs = vec!["DP-1".to_string(), "HDMI-A-1 connected".to_string()];
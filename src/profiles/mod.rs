@@ -2,5 +2,6 @@ mod auto_apply;
 mod model;
 mod store;
 
+pub use auto_apply::choose_profile_for_outputs;
 pub use model::TvProfile;
 pub use store::ProfileStore;
@@ -1,10 +1,14 @@
 use serde::{Deserialize, Serialize};
 
+use crate::core::snapshot::LayoutSnapshot;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TvProfile {
     pub name: String,
     pub hdmi_output: Option<String>,
     pub audio_sink: Option<String>,
+    #[serde(default)]
+    pub layout: Option<LayoutSnapshot>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
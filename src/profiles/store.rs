@@ -50,6 +50,10 @@ impl ProfileStore {
             .find(|profile| profile.name == name))
     }
 
+    pub fn list_profiles(&self) -> anyhow::Result<Vec<TvProfile>> {
+        Ok(self.read_collection()?.profiles)
+    }
+
     fn read_collection(&self) -> anyhow::Result<ProfileCollection> {
         let content = fs::read_to_string(&self.path).context("failed to read profile store")?;
         let parsed = serde_json::from_str(&content).context("failed to parse profiles.json")?;
@@ -1,8 +1,45 @@
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 
-use crate::app::App;
+use crate::app::{App, AudioFocus};
+use crate::ui::{AUDIO_TAB, CAST_TAB, MONITORS_TAB, PROFILES_TAB, WINDOWS_TAB};
 
 pub fn handle_key(app: &mut App, key: KeyEvent) {
+    if app.kdl_export.is_some() {
+        match key.code {
+            KeyCode::Char('y') => app.confirm_kdl_export(),
+            KeyCode::Char('n') | KeyCode::Esc => app.cancel_kdl_export(),
+            _ => {}
+        }
+        return;
+    }
+    if app.pending_revert.is_some() {
+        match key.code {
+            KeyCode::Char('y') => return app.confirm_layout(),
+            KeyCode::Char('n') => return app.revert_layout_now(),
+            _ => {}
+        }
+    }
+
+    if app.selected_tab == CAST_TAB && handle_cast_key(app, key) {
+        return;
+    }
+    if app.selected_tab == MONITORS_TAB && app.layout_editor.is_some() {
+        if handle_layout_editor_key(app, key) {
+            return;
+        }
+    } else if app.selected_tab == MONITORS_TAB && handle_monitors_key(app, key) {
+        return;
+    }
+    if app.selected_tab == WINDOWS_TAB && handle_windows_key(app, key) {
+        return;
+    }
+    if app.selected_tab == AUDIO_TAB && handle_audio_key(app, key) {
+        return;
+    }
+    if app.selected_tab == PROFILES_TAB && key.code == KeyCode::Char('X') {
+        return app.prepare_profile_kdl_export();
+    }
+
     match (key.code, key.modifiers) {
         (KeyCode::Char('q'), _) => app.running = false,
         (KeyCode::Tab, _) | (KeyCode::Right, _) => app.next_tab(),
@@ -12,10 +49,14 @@ pub fn handle_key(app: &mut App, key: KeyEvent) {
         (KeyCode::Char('c'), _) => app.cast_preflight(),
         (KeyCode::Char('e'), _) => app.cast_extend_right(),
         (KeyCode::Char('w'), _) => app.cast_extend_left(),
+        (KeyCode::Char('A'), _) => app.cast_extend_above(),
+        (KeyCode::Char('B'), _) => app.cast_extend_below(),
+        (KeyCode::Char('g'), _) => app.cycle_layout_alignment(),
         (KeyCode::Char('v'), _) => app.cast_mirror(),
         (KeyCode::Char('h'), _) => app.cast_hdmi_only(),
         (KeyCode::Char('u'), _) => app.cast_restore_all(),
-        (KeyCode::Char('m'), _) => app.discover_hdmi_outputs(),
+        (KeyCode::Char('Z'), _) => app.clear_dynamic_cast(),
+        (KeyCode::Char('m'), _) => app.discover_external_outputs(),
         (KeyCode::Char('a'), _) => app.apply_hdmi_audio(),
         (KeyCode::Char('j'), _) => app.select_next_audio_sink(),
         (KeyCode::Char('k'), _) => app.select_prev_audio_sink(),
@@ -28,3 +69,127 @@ pub fn handle_key(app: &mut App, key: KeyEvent) {
         _ => {}
     }
 }
+
+fn handle_cast_key(app: &mut App, key: KeyEvent) -> bool {
+    match key.code {
+        KeyCode::Char('j') => app.select_next_cast_target(),
+        KeyCode::Char('k') => app.select_prev_cast_target(),
+        KeyCode::Char('C') => app.set_dynamic_cast_to_target_output(),
+        KeyCode::Char('f') => app.toggle_audio_follows_display(),
+        _ => return false,
+    }
+    true
+}
+
+fn handle_audio_key(app: &mut App, key: KeyEvent) -> bool {
+    let sources = app.audio_focus == AudioFocus::Sources;
+    let streams = app.audio_focus == AudioFocus::Streams;
+    let cards = app.audio_focus == AudioFocus::Cards;
+    match key.code {
+        KeyCode::Char('i') => app.toggle_audio_focus(),
+        KeyCode::Char('j') if streams => app.select_next_audio_stream(),
+        KeyCode::Char('k') if streams => app.select_prev_audio_stream(),
+        KeyCode::Char(']') if streams => app.cycle_stream_destination(true),
+        KeyCode::Char('[') if streams => app.cycle_stream_destination(false),
+        KeyCode::Enter if streams => app.move_selected_stream(),
+        KeyCode::Char('j') if sources => app.select_next_audio_source(),
+        KeyCode::Char('k') if sources => app.select_prev_audio_source(),
+        KeyCode::Enter if sources => app.apply_selected_audio_source(),
+        KeyCode::Char('j') if cards => app.select_next_audio_card(),
+        KeyCode::Char('k') if cards => app.select_prev_audio_card(),
+        KeyCode::Char(']') if cards => app.cycle_card_profile(true),
+        KeyCode::Char('[') if cards => app.cycle_card_profile(false),
+        KeyCode::Enter if cards => app.apply_selected_card_profile(),
+        KeyCode::Char('+') | KeyCode::Char('=') => app.raise_selected_volume(),
+        KeyCode::Char('-') => app.lower_selected_volume(),
+        KeyCode::Char('M') => app.toggle_selected_mute(),
+        // '1'..'9' set 10%..90%, '0' sets 100%.
+        KeyCode::Char(digit @ '0'..='9') => {
+            let tenth = digit.to_digit(10).unwrap_or(0);
+            app.set_selected_volume(if tenth == 0 { 100 } else { tenth * 10 });
+        }
+        _ => return false,
+    }
+    true
+}
+
+fn handle_monitors_key(app: &mut App, key: KeyEvent) -> bool {
+    match key.code {
+        KeyCode::Char('j') => app.select_next_output(),
+        KeyCode::Char('k') => app.select_prev_output(),
+        KeyCode::Char(']') => app.select_next_mode(),
+        KeyCode::Char('[') => app.select_prev_mode(),
+        KeyCode::Enter => app.apply_selected_mode(),
+        KeyCode::Char('+') | KeyCode::Char('=') => app.increase_selected_scale(),
+        KeyCode::Char('-') => app.decrease_selected_scale(),
+        KeyCode::Char('o') => app.rotate_selected_output(),
+        KeyCode::Char('f') => app.flip_selected_output(),
+        KeyCode::Char('z') => app.cycle_preview_mode(),
+        KeyCode::Char('E') => app.open_layout_editor(),
+        KeyCode::Char('X') => app.prepare_live_kdl_export(),
+        _ => return false,
+    }
+    true
+}
+
+fn handle_windows_key(app: &mut App, key: KeyEvent) -> bool {
+    match key.code {
+        KeyCode::Char('j') => app.select_next_window(),
+        KeyCode::Char('k') => app.select_prev_window(),
+        KeyCode::Enter => app.send_selected_window(),
+        KeyCode::Char('F') => app.send_selected_window_fullscreen(),
+        KeyCode::Char('C') => app.set_dynamic_cast_to_selected_window(),
+        _ => return false,
+    }
+    true
+}
+
+fn handle_layout_editor_key(app: &mut App, key: KeyEvent) -> bool {
+    let fine = key.modifiers.contains(KeyModifiers::SHIFT);
+    match key.code {
+        KeyCode::Left => app.editor_move(-1, 0, fine),
+        KeyCode::Right => app.editor_move(1, 0, fine),
+        KeyCode::Up => app.editor_move(0, -1, fine),
+        KeyCode::Down => app.editor_move(0, 1, fine),
+        KeyCode::Char('j') => app.editor_select_next(),
+        KeyCode::Char('k') => app.editor_select_prev(),
+        KeyCode::Enter => app.apply_layout_editor(),
+        KeyCode::Esc => app.cancel_layout_editor(),
+        _ => return !reaches_global_keys_while_editing(key),
+    }
+    true
+}
+
+/// Quitting and switching tabs still work while a draft is open; every other
+/// global key could change the outputs under it, so it is ignored.
+fn reaches_global_keys_while_editing(key: KeyEvent) -> bool {
+    matches!(
+        (key.code, key.modifiers),
+        (KeyCode::Char('q'), _)
+            | (KeyCode::Tab, _)
+            | (KeyCode::BackTab, _)
+            | (KeyCode::Char('x'), KeyModifiers::CONTROL)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+    use super::reaches_global_keys_while_editing;
+
+    #[test]
+    fn editor_ignores_keys_that_change_outputs() {
+        for c in ['e', 'w', 'A', 'B', 'h', 'v', 'u', 'r', 'g', 'Z', 'X', 'o', 'f'] {
+            let key = KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE);
+            assert!(!reaches_global_keys_while_editing(key), "`{c}` reached the global keys");
+        }
+        for key in [
+            KeyEvent::new(KeyCode::Char('q'), KeyModifiers::NONE),
+            KeyEvent::new(KeyCode::Tab, KeyModifiers::NONE),
+            KeyEvent::new(KeyCode::Char('x'), KeyModifiers::CONTROL),
+        ] {
+            assert!(reaches_global_keys_while_editing(key));
+        }
+    }
+}
@@ -1,8 +1,22 @@
 pub mod events;
+pub mod preview;
 pub mod render;
 pub mod views;
 
 pub use events::handle_key;
 pub use render::render;
 
-pub const TAB_TITLES: [&str; 5] = ["Cast", "Monitors", "Audio", "Profiles", "Troubleshoot"];
+pub const TAB_TITLES: [&str; 6] = [
+    "Cast",
+    "Monitors",
+    "Windows",
+    "Audio",
+    "Profiles",
+    "Troubleshoot",
+];
+
+pub const CAST_TAB: usize = 0;
+pub const MONITORS_TAB: usize = 1;
+pub const WINDOWS_TAB: usize = 2;
+pub const AUDIO_TAB: usize = 3;
+pub const PROFILES_TAB: usize = 4;
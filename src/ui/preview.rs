@@ -0,0 +1,109 @@
+use ratatui::layout::{Constraint, Direction, Layout, Rect as Area};
+use ratatui::style::{Color, Style};
+use ratatui::text::Span;
+use ratatui::widgets::canvas::{Canvas, Rectangle};
+use ratatui::widgets::{Block, Borders};
+use ratatui::Frame;
+
+use crate::app::App;
+use crate::core::layout::PlacedOutput;
+
+/// Draws the current layout and, when a preview mode is picked, the layout
+/// that mode would produce, side by side.
+pub fn render_layout_preview(frame: &mut Frame, app: &App, area: Area) {
+    if let Some(editor) = &app.layout_editor {
+        let title = match editor.selected_output() {
+            Some(output) => format!(
+                "Editing {} at {},{} (Enter apply, Esc cancel)",
+                output.name, output.rect.x, output.rect.y
+            ),
+            None => "Editing".to_string(),
+        };
+        render_canvas(frame, area, &title, &editor.placed());
+        return;
+    }
+
+    let current = app.current_layout();
+    match (app.preview_mode, app.projected_layout()) {
+        (Some(mode), Some(projected)) => {
+            let halves = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+                .split(area);
+            render_canvas(frame, halves[0], "Now", &current);
+            render_canvas(
+                frame,
+                halves[1],
+                &format!("After {}", mode.label()),
+                &projected,
+            );
+        }
+        (Some(mode), None) => {
+            let title = format!("Now (no target for {})", mode.label());
+            render_canvas(frame, area, &title, &current);
+        }
+        (None, _) => render_canvas(frame, area, "Now ('z' preview a cast mode)", &current),
+    }
+}
+
+fn render_canvas(frame: &mut Frame, area: Area, title: &str, outputs: &[PlacedOutput]) {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(title.to_string());
+    if outputs.is_empty() {
+        frame.render_widget(block.title_bottom("no enabled outputs"), area);
+        return;
+    }
+
+    let (x_bounds, y_bounds) = bounds(outputs);
+    let canvas = Canvas::default()
+        .block(block)
+        .x_bounds(x_bounds)
+        .y_bounds(y_bounds)
+        .paint(|ctx| {
+            for output in outputs {
+                let rect = output.rect;
+                let color = if output.is_target {
+                    Color::LightGreen
+                } else {
+                    Color::White
+                };
+                // niri's y axis grows downwards, the canvas y axis grows upwards.
+                let bottom = -f64::from(rect.y + rect.height);
+                ctx.draw(&Rectangle {
+                    x: f64::from(rect.x),
+                    y: bottom,
+                    width: f64::from(rect.width),
+                    height: f64::from(rect.height),
+                    color,
+                });
+                ctx.print(
+                    f64::from(rect.x) + f64::from(rect.width) * 0.05,
+                    -f64::from(rect.y) - f64::from(rect.height) * 0.2,
+                    Span::styled(output.name.clone(), Style::default().fg(color)),
+                );
+            }
+        });
+    frame.render_widget(canvas, area);
+}
+
+fn bounds(outputs: &[PlacedOutput]) -> ([f64; 2], [f64; 2]) {
+    let min_x = outputs.iter().map(|o| o.rect.x).min().unwrap_or(0);
+    let max_x = outputs
+        .iter()
+        .map(|o| o.rect.x + o.rect.width)
+        .max()
+        .unwrap_or(1);
+    let min_y = outputs.iter().map(|o| o.rect.y).min().unwrap_or(0);
+    let max_y = outputs
+        .iter()
+        .map(|o| o.rect.y + o.rect.height)
+        .max()
+        .unwrap_or(1);
+
+    let margin = f64::from((max_x - min_x).max(max_y - min_y)) * 0.05;
+    (
+        [f64::from(min_x) - margin, f64::from(max_x) + margin],
+        [-f64::from(max_y) - margin, -f64::from(min_y) + margin],
+    )
+}
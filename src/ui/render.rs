@@ -1,11 +1,11 @@
-use ratatui::layout::{Constraint, Direction, Layout};
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
 use ratatui::style::{Color, Modifier, Style};
 use ratatui::text::{Line, Span};
-use ratatui::widgets::{Block, Borders, Paragraph, Tabs, Wrap};
+use ratatui::widgets::{Block, Borders, Gauge, Paragraph, Tabs, Wrap};
 use ratatui::Frame;
 
-use crate::app::App;
-use crate::ui::TAB_TITLES;
+use crate::app::{App, AudioFocus};
+use crate::ui::{AUDIO_TAB, MONITORS_TAB, TAB_TITLES};
 
 pub fn render(frame: &mut Frame, app: &App) {
     let chunks = Layout::default()
@@ -30,10 +30,59 @@ pub fn render(frame: &mut Frame, app: &App) {
     frame.render_widget(tabs, chunks[0]);
 
     let main_content = super::views::main_content(app);
-    let main = Paragraph::new(main_content)
-        .block(Block::default().borders(Borders::ALL).title("Control"))
-        .wrap(Wrap { trim: true });
-    frame.render_widget(main, chunks[1]);
+    let main_block = match &app.pending_revert {
+        _ if app.kdl_export.is_some() => Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Yellow))
+            .title(Span::styled(
+                "Write these changes to config.kdl? ('y' write / 'n' cancel)",
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+            )),
+        Some(pending) => Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Yellow))
+            .title(Span::styled(
+                format!(
+                    "Keep this configuration? Reverting in {}s ('y' keep / 'n' revert)",
+                    pending.seconds_left()
+                ),
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+            )),
+        None => Block::default().borders(Borders::ALL).title("Control"),
+    };
+    if app.selected_tab == MONITORS_TAB && app.kdl_export.is_none() {
+        let inner = main_block.inner(chunks[1]);
+        frame.render_widget(main_block, chunks[1]);
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+            .split(inner);
+        let main = Paragraph::new(main_content).wrap(Wrap { trim: true });
+        frame.render_widget(main, columns[0]);
+        super::preview::render_layout_preview(frame, app, columns[1]);
+    } else if app.selected_tab == AUDIO_TAB && app.kdl_export.is_none() {
+        let inner = main_block.inner(chunks[1]);
+        frame.render_widget(main_block, chunks[1]);
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Min(3),
+                Constraint::Length((app.audio_sinks.len() + app.audio_sources.len() + 1) as u16),
+            ])
+            .split(inner);
+        let main = Paragraph::new(main_content).wrap(Wrap { trim: true });
+        frame.render_widget(main, rows[0]);
+        render_volume_gauges(frame, app, rows[1]);
+    } else {
+        let main = Paragraph::new(main_content)
+            .block(main_block)
+            .wrap(Wrap { trim: true });
+        frame.render_widget(main, chunks[1]);
+    }
 
     let log_lines = app
         .log_lines
@@ -48,21 +97,83 @@ pub fn render(frame: &mut Frame, app: &App) {
         .wrap(Wrap { trim: false });
     frame.render_widget(logs, chunks[2]);
 
-    let footer = Paragraph::new(vec![Line::from(vec![
-        Span::styled("q", Style::default().fg(Color::Yellow)),
-        Span::raw(" quit  "),
-        Span::styled("Tab", Style::default().fg(Color::Yellow)),
-        Span::raw(" switch tab  "),
-        Span::styled("r", Style::default().fg(Color::Yellow)),
-        Span::raw(" refresh  "),
-        Span::styled("d", Style::default().fg(Color::Yellow)),
-        Span::raw(" diagnostics  "),
-        Span::styled("c/e/w/v/h/u", Style::default().fg(Color::Yellow)),
-        Span::raw(" preflight/extendR/extendL/mirror/hdmi-only/restore  "),
-        Span::styled("m/a/s/l", Style::default().fg(Color::Yellow)),
-        Span::raw(" outputs/hdmi-audio/save/load  "),
-        Span::styled("j/k/Enter/t/p", Style::default().fg(Color::Yellow)),
-        Span::raw(" audio-select/apply/tv/laptop"),
-    ])]);
+    let footer = Paragraph::new(vec![
+        Line::from(vec![
+            Span::styled("q", Style::default().fg(Color::Yellow)),
+            Span::raw(" quit  "),
+            Span::styled("Tab", Style::default().fg(Color::Yellow)),
+            Span::raw(" switch tab  "),
+            Span::styled("r", Style::default().fg(Color::Yellow)),
+            Span::raw(" refresh  "),
+            Span::styled("d", Style::default().fg(Color::Yellow)),
+            Span::raw(" diagnostics  "),
+            Span::styled("c/e/w/A/B/g/v/h/u", Style::default().fg(Color::Yellow)),
+            Span::raw(" preflight/extendR/extendL/above/below/align/mirror/hdmi-only/restore  "),
+            Span::styled("m/a/s/l", Style::default().fg(Color::Yellow)),
+            Span::raw(" outputs/hdmi-audio/save/load  "),
+            Span::styled("j/k/Enter/t/p", Style::default().fg(Color::Yellow)),
+            Span::raw(" audio-select/apply/tv/laptop"),
+        ]),
+        Line::from(vec![
+            Span::styled("i", Style::default().fg(Color::Yellow)),
+            Span::raw(" audio-focus  "),
+            Span::styled("[/]", Style::default().fg(Color::Yellow)),
+            Span::raw(" stream-sink/card-profile  "),
+            Span::styled("+/-/0-9", Style::default().fg(Color::Yellow)),
+            Span::raw(" volume  "),
+            Span::styled("M", Style::default().fg(Color::Yellow)),
+            Span::raw(" mute"),
+        ]),
+    ]);
     frame.render_widget(footer, chunks[3]);
 }
+
+/// One volume gauge per sink, then one per source after a blank row, in
+/// the same order as the lists above them.
+fn render_volume_gauges(frame: &mut Frame, app: &App, area: Rect) {
+    let sinks_focused = app.audio_focus == AudioFocus::Sinks;
+    let mut gauges = app
+        .audio_sinks
+        .iter()
+        .enumerate()
+        .map(|(idx, sink)| {
+            let selected = sinks_focused && idx == app.selected_audio_sink;
+            Some((sink.name.as_str(), sink.volume, sink.muted, selected))
+        })
+        .collect::<Vec<_>>();
+    gauges.push(None);
+    let sources_focused = app.audio_focus == AudioFocus::Sources;
+    gauges.extend(app.audio_sources.iter().enumerate().map(|(idx, source)| {
+        let selected = sources_focused && idx == app.selected_audio_source;
+        Some((source.name.as_str(), source.volume, source.muted, selected))
+    }));
+
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(vec![Constraint::Length(1); gauges.len()])
+        .split(area);
+    for (gauge, row) in gauges.into_iter().zip(rows.iter()) {
+        let Some((name, volume, muted, selected)) = gauge else {
+            continue;
+        };
+        let level = if muted {
+            "muted".to_string()
+        } else if let Some(volume) = volume {
+            format!("{:.0}%", volume * 100.0)
+        } else {
+            "no volume".to_string()
+        };
+        let color = if muted {
+            Color::DarkGray
+        } else if selected {
+            Color::LightGreen
+        } else {
+            Color::Cyan
+        };
+        let gauge = Gauge::default()
+            .gauge_style(Style::default().fg(color))
+            .ratio(f64::from(volume.unwrap_or(0.0)).clamp(0.0, 1.0))
+            .label(format!("{name} {level}"));
+        frame.render_widget(gauge, *row);
+    }
+}
@@ -1,13 +1,23 @@
-use ratatui::text::{Line, Text};
+use ratatui::style::{Color, Style};
+use ratatui::text::{Line, Span, Text};
 
-use crate::app::App;
+use crate::app::{App, AudioFocus};
+use crate::core::cast::DynamicCastTarget;
+use crate::core::niri_config::KdlExport;
+use crate::ui::{AUDIO_TAB, CAST_TAB, MONITORS_TAB, PROFILES_TAB, WINDOWS_TAB};
+
+const MODE_WINDOW: usize = 8;
 
 pub fn main_content(app: &App) -> Text<'static> {
+    if let Some(export) = &app.kdl_export {
+        return kdl_export_view(export);
+    }
     match app.selected_tab {
-        0 => cast_view(app),
-        1 => monitors_view(app),
-        2 => audio_view(app),
-        3 => profiles_view(app),
+        CAST_TAB => cast_view(app),
+        MONITORS_TAB => monitors_view(app),
+        WINDOWS_TAB => windows_view(app),
+        AUDIO_TAB => audio_view(app),
+        PROFILES_TAB => profiles_view(app),
         _ => troubleshoot_view(app),
     }
 }
@@ -18,12 +28,69 @@ fn cast_view(app: &App) -> Text<'static> {
         Line::from("Press 'c' to run cast preflight checks."),
         Line::from("Press 'e' for extend-right mode."),
         Line::from("Press 'w' for extend-left mode."),
-        Line::from("Press 'v' for mirror mode (wl-mirror fullscreen on HDMI)."),
-        Line::from("Press 'h' for HDMI-only mode."),
-        Line::from("Press 'u' to restore all connected outputs (turn on + auto position)."),
+        Line::from("Press 'A' for extend-above, 'B' for extend-below mode."),
+        Line::from(format!(
+            "Press 'g' to cycle extend alignment (current: {}).",
+            app.layout_alignment.label()
+        )),
+        Line::from("Press 'v' for mirror mode (wl-mirror fullscreen on the target)."),
+        Line::from("Press 'h' for target-only mode (other outputs off)."),
+        Line::from("Press 'u' to restore the layout saved before the first cast mode."),
+        Line::from("Use 'j'/'k' to choose the cast target."),
+        Line::from("Press 'C' to share the target in screencasts, 'Z' to clear the dynamic cast target."),
+        Line::from(format!(
+            "Press 'f' to toggle audio following the display (current: {}).",
+            if app.config.audio_follows_display { "on" } else { "off" }
+        )),
+        Line::from(""),
+        Line::from(format!("Cast state: {}", app.cast_state)),
+        Line::from(format!(
+            "Cast audio: {}",
+            app.tv_audio
+                .as_ref()
+                .map(|tv_audio| format!("{} on {}", tv_audio.sink.name, tv_audio.output))
+                .unwrap_or_else(|| "not switched by niri-cast".to_string())
+        )),
+        Line::from(format!(
+            "Dynamic cast target: {}",
+            app.dynamic_cast
+                .as_ref()
+                .map(|target| target.describe())
+                .unwrap_or_else(|| "not set by niri-cast".to_string())
+        )),
         Line::from(""),
     ];
 
+    let target = app.cast_target_name();
+    let externals = app.external_outputs();
+    if externals.is_empty() {
+        lines.push(Line::from("Cast target: no external output connected"));
+    } else {
+        lines.push(Line::from("Cast target:"));
+        for output in externals {
+            let cursor = if target.as_deref() == Some(output.name.as_str()) {
+                ">"
+            } else {
+                " "
+            };
+            lines.push(Line::from(format!(
+                "{cursor} {} — {}",
+                output.name,
+                output.description()
+            )));
+        }
+    }
+    lines.push(Line::from(""));
+
+    if let Some(pending) = &app.pending_revert {
+        lines.push(Line::from(format!(
+            "Pending: {} — reverting in {}s unless confirmed with 'y'.",
+            pending.description,
+            pending.seconds_left()
+        )));
+        lines.push(Line::from(""));
+    }
+
     if let Some(report) = &app.diagnostics {
         lines.push(Line::from(format!(
             "Latest diagnostics: {} ok / {} warn / {} error",
@@ -38,33 +105,141 @@ fn cast_view(app: &App) -> Text<'static> {
 
 fn monitors_view(app: &App) -> Text<'static> {
     let mut lines = vec![
-        Line::from("Monitor control via `niri msg` wrappers."),
-        Line::from("Press 'm' to list HDMI outputs."),
+        Line::from("Monitor control via niri IPC."),
+        Line::from("Press 'm' to list external outputs."),
+        Line::from("Use 'j'/'k' to select an output, '['/']' to pick a mode, Enter to apply it."),
+        Line::from("Use '+'/'-' to change scale, 'o' to rotate, 'f' to flip the selected output."),
+        Line::from("Press 'E' to arrange outputs by hand, 'z' to preview a cast mode."),
+        Line::from("Press 'X' to persist the live layout into niri's config.kdl."),
         Line::from(""),
         Line::from("Discovered outputs:"),
     ];
-    if app.last_outputs.is_empty() {
+    if app.outputs.is_empty() {
         lines.push(Line::from("- none"));
-    } else {
-        lines.extend(app.last_outputs.iter().map(|x| Line::from(format!("- {x}"))));
+    }
+    for (idx, output) in app.outputs.iter().enumerate() {
+        let cursor = if idx == app.selected_output { ">" } else { "-" };
+        lines.push(Line::from(format!(
+            "{cursor} {} — {}",
+            output.name,
+            output.description()
+        )));
+
+        let mode = output
+            .current_mode()
+            .map(|mode| mode.label())
+            .unwrap_or_else(|| "no mode".to_string());
+        match &output.logical {
+            Some(logical) => lines.push(Line::from(format!(
+                "    {mode}  scale {}  transform {}  at {},{} ({}x{} logical)",
+                logical.scale,
+                logical.transform.as_str(),
+                logical.x,
+                logical.y,
+                logical.width,
+                logical.height
+            ))),
+            None => lines.push(Line::from(format!("    {mode}  disabled"))),
+        }
+
+        let physical = output
+            .physical_size
+            .map(|(w, h)| format!("{w}x{h} mm"))
+            .unwrap_or_else(|| "unknown size".to_string());
+        let vrr = match (output.vrr_supported, output.vrr_enabled) {
+            (false, _) => "unsupported",
+            (true, true) => "enabled",
+            (true, false) => "disabled",
+        };
+        lines.push(Line::from(format!(
+            "    {physical}  {} mode(s)  VRR {vrr}",
+            output.modes.len()
+        )));
+    }
+
+    if let Some(output) = app.selected_output() {
+        lines.push(Line::from(""));
+        lines.push(Line::from(format!("Modes for {} (* current, + preferred):", output.name)));
+        let window = MODE_WINDOW.min(output.modes.len());
+        let start = app
+            .selected_mode
+            .saturating_sub(window / 2)
+            .min(output.modes.len() - window);
+        for (idx, mode) in output.modes.iter().enumerate().skip(start).take(window) {
+            let cursor = if idx == app.selected_mode { ">" } else { " " };
+            let current = if output.current_mode == Some(idx) { "*" } else { " " };
+            let preferred = if mode.is_preferred { "+" } else { " " };
+            lines.push(Line::from(format!(
+                "{cursor} [{current}{preferred}] {}",
+                mode.label()
+            )));
+        }
+    }
+    Text::from(lines)
+}
+
+fn windows_view(app: &App) -> Text<'static> {
+    let target = app.cast_target_name();
+    let mut lines = vec![
+        Line::from("Send a window to the cast target."),
+        Line::from(format!(
+            "Target: {}",
+            target.as_deref().unwrap_or("none connected")
+        )),
+        Line::from("Use 'j'/'k' to select, Enter to send, 'F' to send and toggle fullscreen, 'r' to refresh."),
+        Line::from("Press 'C' to make the selected window the dynamic screencast target."),
+        Line::from(""),
+    ];
+    if app.windows.is_empty() {
+        lines.push(Line::from("- no windows"));
+    }
+    for (idx, window) in app.windows.iter().enumerate() {
+        let cursor = if idx == app.selected_window { ">" } else { "-" };
+        let workspace = window
+            .workspace_id
+            .and_then(|id| app.workspaces.iter().find(|w| w.id == id));
+        let place = match workspace {
+            Some(workspace) => format!(
+                "workspace {} on {}",
+                workspace.label(),
+                workspace.output.as_deref().unwrap_or("no output")
+            ),
+            None => "no workspace".to_string(),
+        };
+        let focus = if window.is_focused { " (focused)" } else { "" };
+        let shared = match &app.dynamic_cast {
+            Some(DynamicCastTarget::Window { id, .. }) if *id == window.id => " (shared)",
+            _ => "",
+        };
+        lines.push(Line::from(format!(
+            "{cursor} {}{focus}{shared} — {place}",
+            window.label()
+        )));
     }
     Text::from(lines)
 }
 
 fn audio_view(app: &App) -> Text<'static> {
     let mut lines = vec![
-        Line::from("Audio control via `wpctl` wrappers."),
+        Line::from("Audio devices from `pw-dump`, switched with `wpctl`."),
         Line::from("Press 'a' to switch to first HDMI sink (TV quick switch)."),
         Line::from("Press 't' for TV audio, 'p' for laptop audio quick switch."),
-        Line::from("Use 'j'/'k' to select a sink, Enter to apply selected sink."),
+        Line::from("Use 'j'/'k' to select, Enter to make it the default, 'i' to switch lists."),
+        Line::from("Streams list: '['/']' pick the destination sink, Enter moves the stream."),
+        Line::from("Cards list: '['/']' pick a profile, Enter switches the card to it."),
+        Line::from("Volume: '+'/'-' step 5%, '1'..'9' set 10%..90%, '0' sets 100%, 'M' mute."),
         Line::from(""),
-        Line::from("Discovered audio output channels:"),
     ];
+    let sinks_focused = app.audio_focus == AudioFocus::Sinks;
+    lines.push(Line::from(format!(
+        "Discovered audio output channels:{}",
+        if sinks_focused { " (selected)" } else { "" }
+    )));
     if app.audio_sinks.is_empty() {
         lines.push(Line::from("- none"));
     } else {
         for (idx, sink) in app.audio_sinks.iter().enumerate() {
-            let cursor = if idx == app.selected_audio_sink { ">" } else { " " };
+            let cursor = if sinks_focused && idx == app.selected_audio_sink { ">" } else { " " };
             let default = if sink.is_default { "*" } else { " " };
             let hdmi = if sink.is_hdmi { "TV/HDMI" } else { "Laptop/Analog" };
             lines.push(Line::from(format!(
@@ -73,6 +248,81 @@ fn audio_view(app: &App) -> Text<'static> {
             )));
         }
     }
+    lines.push(Line::from(""));
+    let sources_focused = app.audio_focus == AudioFocus::Sources;
+    lines.push(Line::from(format!(
+        "Discovered audio input channels:{}",
+        if sources_focused { " (selected)" } else { "" }
+    )));
+    if app.audio_sources.is_empty() {
+        lines.push(Line::from("- none"));
+    } else {
+        for (idx, source) in app.audio_sources.iter().enumerate() {
+            let cursor = if sources_focused && idx == app.selected_audio_source { ">" } else { " " };
+            let default = if source.is_default { "*" } else { " " };
+            lines.push(Line::from(format!("{cursor} [{default}] {}. {}", source.id, source.name)));
+        }
+    }
+    lines.push(Line::from(""));
+    let streams_focused = app.audio_focus == AudioFocus::Streams;
+    lines.push(Line::from(format!(
+        "Playback streams:{}",
+        if streams_focused { " (selected)" } else { "" }
+    )));
+    if app.audio_streams.is_empty() {
+        lines.push(Line::from("- none"));
+    } else {
+        for (idx, stream) in app.audio_streams.iter().enumerate() {
+            let selected = streams_focused && idx == app.selected_audio_stream;
+            let cursor = if selected { ">" } else { " " };
+            let sink = stream
+                .sink_id
+                .as_ref()
+                .and_then(|id| app.audio_sinks.iter().find(|sink| sink.id == *id))
+                .map(|sink| sink.name.as_str())
+                .unwrap_or("no sink");
+            let destination = app
+                .audio_sinks
+                .get(app.selected_audio_sink)
+                .filter(|dest| selected && stream.sink_id.as_deref() != Some(dest.id.as_str()))
+                .map(|dest| format!("  → Enter moves it to {}", dest.name))
+                .unwrap_or_default();
+            lines.push(Line::from(format!(
+                "{cursor} {} on {sink}{destination}",
+                stream.label()
+            )));
+        }
+    }
+    lines.push(Line::from(""));
+    let cards_focused = app.audio_focus == AudioFocus::Cards;
+    lines.push(Line::from(format!(
+        "Sound cards:{}",
+        if cards_focused { " (selected)" } else { "" }
+    )));
+    if app.audio_cards.is_empty() {
+        lines.push(Line::from("- none"));
+    } else {
+        for (idx, card) in app.audio_cards.iter().enumerate() {
+            let selected = cards_focused && idx == app.selected_audio_card;
+            let cursor = if selected { ">" } else { " " };
+            let active = card
+                .active_profile()
+                .map(|profile| profile.name.as_str())
+                .unwrap_or("no profile");
+            lines.push(Line::from(format!("{cursor} {}. {} ({active})", card.id, card.name)));
+            if !selected {
+                continue;
+            }
+            for (profile_idx, profile) in card.profiles.iter().enumerate() {
+                let cursor = if profile_idx == app.selected_card_profile { ">" } else { " " };
+                let active = if card.active_profile == Some(profile.index) { "*" } else { " " };
+                lines.push(Line::from(format!(
+                    "    {cursor} [{active}] {}",
+                    profile.display_line()
+                )));
+            }
+        }
+    }
     Text::from(lines)
 }
 
@@ -81,11 +331,33 @@ fn profiles_view(_app: &App) -> Text<'static> {
         Line::from("Profile actions:"),
         Line::from("- 's': save profile as default-tv"),
         Line::from("- 'l': load profile default-tv"),
+        Line::from("- 'X': write the profile's layout into niri's config.kdl"),
         Line::from(""),
         Line::from("Profiles are stored under XDG config directory."),
     ])
 }
 
+fn kdl_export_view(export: &KdlExport) -> Text<'static> {
+    let mut lines = vec![
+        Line::from(format!(
+            "Export {} to {}",
+            export.source,
+            export.file.path().display()
+        )),
+        Line::from("The current file is backed up first. Press 'y' to write, 'n' to cancel."),
+        Line::from(""),
+    ];
+    lines.extend(export.diff.iter().map(|line| {
+        let color = if line.starts_with('+') {
+            Color::LightGreen
+        } else {
+            Color::LightRed
+        };
+        Line::from(Span::styled(line.clone(), Style::default().fg(color)))
+    }));
+    Text::from(lines)
+}
+
 fn troubleshoot_view(app: &App) -> Text<'static> {
     let mut lines = vec![
         Line::from("Troubleshooting checks:"),